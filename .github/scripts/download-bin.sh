@@ -66,14 +66,19 @@ if [[ "$OS" == "macos-latest" ]]; then
   unzip -o  src-tauri/resources/$ASSET_FILE -d src-tauri/resources
   echo "✅ host_server 解压成功"
   chmod +x src-tauri/resources/host_server_macos
+  HOST_SERVER_DIR="src-tauri/resources/host_server_macos"
 elif [[ "$OS" == "windows-latest" ]]; then
   powershell.exe -Command "Expand-Archive -Path 'src-tauri/resources/$ASSET_FILE' -DestinationPath 'src-tauri/resources' -Force"
   echo "✅ host_server 解压成功"
   echo "✅ Windows 平台，无需执行 chmod"
+  HOST_SERVER_DIR="src-tauri/resources/host_server_windows"
 else
   echo "⚠️ 不识别的系统 $OS"
 fi
 
+# 生成校验清单；macOS 的二进制会在 sign-all.sh 中重新签名，届时在 .app 内重新生成
+bash .github/scripts/host-server-manifest.sh write "$HOST_SERVER_DIR"
+
 rm -rf src-tauri/resources/$ASSET_FILE
 echo "✅ host_server 已下载并解压"
 
@@ -0,0 +1,81 @@
+#!/bin/bash
+set -e
+
+# 生成 / 校验 host_server 校验清单 host_server.manifest.json（sha256 + 版本），应用启动 host_server 前会据此校验
+# 用法：host-server-manifest.sh write|verify <host_server_* 目录>
+# 清单必须对应最终打包的二进制：macOS 在 sign-all.sh 重新签名之后重新生成
+
+MODE=$1
+DIR=$2
+
+if [ -z "$MODE" ] || [ -z "$DIR" ]; then
+  echo "❌ 用法: $0 write|verify <host_server 目录>"
+  exit 1
+fi
+
+NAME=$(basename "$DIR")
+BIN="$DIR/$NAME"
+if [ -f "$BIN.exe" ]; then
+  BIN="$BIN.exe"
+fi
+if [ ! -f "$BIN" ]; then
+  echo "❌ 未找到 host_server 二进制: $BIN"
+  exit 1
+fi
+
+MANIFEST="$DIR/host_server.manifest.json"
+VERSION=$(tr -d '[:space:]' < .host_server_version)
+
+sha256() {
+  if command -v sha256sum > /dev/null 2>&1; then
+    sha256sum "$1" | awk '{print $1}'
+  else
+    shasum -a 256 "$1" | awk '{print $1}'
+  fi
+}
+
+ACTUAL_SHA256=$(sha256 "$BIN")
+
+if [ "$MODE" == "write" ]; then
+  cat > "$MANIFEST" <<EOF
+{
+  "version": "$VERSION",
+  "sha256": "$ACTUAL_SHA256"
+}
+EOF
+  echo "✅ 校验清单已生成: $MANIFEST (version=$VERSION, sha256=$ACTUAL_SHA256)"
+  exit 0
+fi
+
+if [ "$MODE" != "verify" ]; then
+  echo "❌ 未知模式: $MODE"
+  exit 1
+fi
+
+if [ ! -f "$MANIFEST" ]; then
+  echo "❌ 未找到校验清单: $MANIFEST"
+  exit 1
+fi
+
+EXPECTED_SHA256=$(jq -r .sha256 "$MANIFEST")
+EXPECTED_VERSION=$(jq -r .version "$MANIFEST")
+
+# 与 integrity.rs 一致：sha256 忽略大小写
+if [ "$(echo "$EXPECTED_SHA256" | tr '[:upper:]' '[:lower:]')" != "$(echo "$ACTUAL_SHA256" | tr '[:upper:]' '[:lower:]')" ]; then
+  echo "❌ sha256 不一致: 清单 $EXPECTED_SHA256, 实际 $ACTUAL_SHA256（签名是否在生成清单之后改动了二进制？）"
+  exit 1
+fi
+
+if [ "$EXPECTED_VERSION" != "$VERSION" ]; then
+  echo "❌ 清单版本 $EXPECTED_VERSION 与 .host_server_version ($VERSION) 不一致"
+  exit 1
+fi
+
+# 与 integrity.rs 一致：忽略前缀 v，第一行中任意一个词匹配即可
+VERSION_OUTPUT=$("$BIN" --version | head -n 1)
+if ! echo "$VERSION_OUTPUT" | tr -s ' \t' '\n' | sed 's/^v//' | grep -qx "${VERSION#v}"; then
+  echo "❌ \`--version\` 输出 \"$VERSION_OUTPUT\"，期望 $VERSION"
+  exit 1
+fi
+
+echo "✅ host_server 校验通过: $BIN (version=$VERSION, sha256=$ACTUAL_SHA256)"
@@ -87,6 +87,9 @@ done
 # 2.1 签名 host_server_macos 子内容（.so / .dylib / .node / 子可执行文件等）
 sign_host_server
 
+# 2.2 重新签名改变了 host_server 的 sha256，在签整个 .app 之前重新生成校验清单
+bash .github/scripts/host-server-manifest.sh write "$RESOURCES_DIR/host_server_macos"
+
 # 3. 签名完整的 .app
 echo "🔏 Signing entire app bundle..."
 codesign --force --deep --options runtime --sign "$SIGN_IDENTITY" --timestamp --verbose=4 "$APP_PATH"
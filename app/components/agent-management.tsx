@@ -478,14 +478,21 @@ export default function AgentManagement() {
   const [searchParams] = params;
   const id = searchParams.get("id");
   const initModelList = useAppConfig((s) => s.initModelList);
-  const [agents, deleteAgent, handleModel, reRenderAgentList] = useAgentStore(
-    (state) => [
-      state.renderAgents,
-      state.deleteAgent,
-      state.handleModel,
-      state.reRenderAgentList,
-    ],
-  );
+  const [
+    agents,
+    invalidAgents,
+    deleteAgent,
+    deleteInvalidAgent,
+    handleModel,
+    reRenderAgentList,
+  ] = useAgentStore((state) => [
+    state.renderAgents,
+    state.config?.invalid || [],
+    state.deleteAgent,
+    state.deleteInvalidAgent,
+    state.handleModel,
+    state.reRenderAgentList,
+  ]);
 
   useEffect(() => {
     // always request new model data
@@ -496,6 +503,8 @@ export default function AgentManagement() {
 
   const [showDeleteDialog, setShowDeleteDialog] = useState(false);
   const [deletedId, setDeletedId] = useState("");
+  // Index into config.invalid when deleting an invalid agent
+  const [deletedInvalid, setDeletedInvalid] = useState<number | null>(null);
   useEffect(() => {
     if (id) {
       const found = agents.find((item) => item.id === id);
@@ -524,6 +533,31 @@ export default function AgentManagement() {
             <span>{t("agent.add")}</span>
           </Button>
         </div>
+        {invalidAgents.length > 0 && (
+          <div className="mb-4 flex flex-col gap-2">
+            <div className="text-sm text-red-500">{t("agent.invalid")}</div>
+            {invalidAgents.map((agent, index) => (
+              <div
+                key={index}
+                className="flex justify-between items-center gap-2 rounded-sm border border-red-500/20 px-2.5 py-1.5 text-sm"
+              >
+                <span className="font-medium truncate">
+                  {String(agent.agent_id ?? agent.agent_name ?? `#${index + 1}`)}
+                </span>
+                <Button
+                  className="h-7 shrink-0 text-sm rounded-sm px-2.5 py-1"
+                  variant="outline"
+                  onClick={() => {
+                    setDeletedInvalid(index);
+                    setShowDeleteDialog(true);
+                  }}
+                >
+                  {t("general:dialog.delete")}
+                </Button>
+              </div>
+            ))}
+          </div>
+        )}
         <AgentList
           agents={agents}
           onEdit={(agent) => {
@@ -566,13 +600,21 @@ export default function AgentManagement() {
             </AlertDialogDescription>
             <AlertDialogFooter>
               <AlertDialogCancel
-                onClick={() => setDeletedId("")}
+                onClick={() => {
+                  setDeletedId("");
+                  setDeletedInvalid(null);
+                }}
                 className="flex-1 rounded-sm hover:bg-[#F3F5F74D] border border-[#E8ECEF] dark:border-[#343839] font-medium"
               >
                 {t("general:dialog.cancel")}
               </AlertDialogCancel>
               <AlertDialogAction
                 onClick={() => {
+                  if (deletedInvalid !== null) {
+                    deleteInvalidAgent(deletedInvalid);
+                    setDeletedInvalid(null);
+                    return;
+                  }
                   deleteAgent(deletedId);
                   setDeletedId("");
                   setShowEdit(false);
@@ -113,7 +113,10 @@ function ServerTable({
 const McpTable: React.FC<Props> = ({ setMode, setDetail }) => {
   const mcpStore = useMcpStore();
   const { t } = useTranslation("settings");
-  const { switchMcpStatus, removeMcpItem, updateMcpArgsEnvs } = mcpStore;
+  const { switchMcpStatus, removeMcpItem, removeInvalidMcpItem, updateMcpArgsEnvs } =
+    mcpStore;
+  const invalidServers = useMcpStore((state) => state.config?.invalid || {});
+  const configErrors = useMcpStore((state) => state.config?.errors || []);
   const [searchValue, setSearchValue] = useState("");
   const [showSettingModal, setShowSettingModal] = useState(false);
   const renderMcpList = useMcpStore((state) => state.renderMcpList);
@@ -122,6 +125,20 @@ const McpTable: React.FC<Props> = ({ setMode, setDetail }) => {
   );
   const [detailInfo, setDetailInfo] = useState<McpItemInfo>();
   const [currentMcpName, setCurrentMcpName] = useState<string>("");
+  const handleDeleteInvalid = async (name: string) => {
+    const result = await showConfirm({
+      title: t("mcp.delete.title"),
+      description: t("mcp.delete.description"),
+      type: "delete",
+    });
+    if (result !== ConfirmType.Confirm) return;
+    try {
+      await removeInvalidMcpItem(name);
+      toast.success(t("mcp.delete.success"));
+    } catch {
+      toast.error(t("mcp.delete.fail"));
+    }
+  };
   const handleSettingConfirm = async (update: TSettingInfo) => {
     setShowSettingModal(false);
     try {
@@ -161,6 +178,34 @@ const McpTable: React.FC<Props> = ({ setMode, setDetail }) => {
         className="scroll-container h-full -mr-2"
         style={{ maxHeight: "calc(100% - 80px)" }}
       >
+        {Object.keys(invalidServers).length > 0 && (
+          <div className="mb-4 flex flex-col gap-2">
+            <div className="text-sm text-red-500">{t("mcp.invalid.title")}</div>
+            {Object.keys(invalidServers).map((name) => (
+              <div
+                key={name}
+                className="flex justify-between items-center gap-2 rounded-sm border border-red-500/20 px-2.5 py-1.5 text-sm"
+              >
+                <div className="flex flex-col min-w-0">
+                  <span className="font-medium">{name}</span>
+                  <span className="text-xs text-[#6C7275] truncate">
+                    {configErrors
+                      .filter((error) => error.server === name)
+                      .map((error) => `${error.field}: ${error.message}`)
+                      .join("; ")}
+                  </span>
+                </div>
+                <Button
+                  className="h-7 shrink-0 text-sm rounded-sm px-2.5 py-1"
+                  variant="outline"
+                  onClick={() => handleDeleteInvalid(name)}
+                >
+                  {t("mcp.delete.title")}
+                </Button>
+              </div>
+            ))}
+          </div>
+        )}
         <ServerTable
           servers={renderMcpList.filter((item) =>
             item.mcp_name.toLowerCase().includes(searchValue.toLowerCase()),
@@ -13,8 +13,7 @@ export function useGetToken() {
     async function getToken() {
       try {
         const token = await getLocalToken();
-        const { data } = token;
-        config.setLocalToken(data);
+        config.setLocalToken(token);
       } catch (error) {
         console.error("getLocalToken error", JSON.stringify(error));
       }
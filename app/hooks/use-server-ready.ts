@@ -2,6 +2,7 @@ import { useEffect } from "react";
 import { listen } from "@tauri-apps/api/event";
 import { HOST_SERVER_READY_EVENT } from "../constant";
 import { useAppConfig } from "../store/config";
+import { hostServerRestarted } from "../utils/init";
 
 const LOADING_TIMEOUT = Number(
   process.env.NEXT_PUBLIC_LOADING_TIMEOUT || 40000,
@@ -9,6 +10,11 @@ const LOADING_TIMEOUT = Number(
 
 let resolved = false;
 
+interface HostServerReady {
+  port: number;
+  url: string;
+}
+
 export function useHostServerReady(onReady: (ready: boolean) => void) {
   const config = useAppConfig.getState();
   useEffect(() => {
@@ -21,17 +27,20 @@ export function useHostServerReady(onReady: (ready: boolean) => void) {
     }, LOADING_TIMEOUT);
 
     const unlistenPromise = listen(HOST_SERVER_READY_EVENT, (event) => {
-      const port = event.payload as number | undefined;
-      console.log("Host server ready, port ", port);
+      const { port, url } = (event.payload ?? {}) as HostServerReady;
+      console.log("Host server ready at", url);
 
-      if (!port) {
-        console.log("Host server ready, but port is undefined");
+      if (!port || !url) {
+        console.log("Host server ready, but port or url is undefined");
         clearTimeout(timeout);
         onReady(false);
         return;
       }
       config.setHostPort(port);
-      if (!resolved) {
+      config.setHostServerUrl(url);
+      if (resolved) {
+        hostServerRestarted();
+      } else {
         resolved = true;
         clearTimeout(timeout);
         console.log("Host server start ready");
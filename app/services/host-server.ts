@@ -1,4 +1,5 @@
 import { Body } from "@tauri-apps/api/http";
+import { invoke } from "@tauri-apps/api/tauri";
 import { aidenFetch as fetch, getLocalBaseDomain } from "@/app/utils/fetch";
 import { fetchNoProxy } from "@/app/utils/fetch-no-proxy";
 import { getHeaders } from "@/app/utils/fetch";
@@ -7,7 +8,16 @@ const remoteMcpURL = "/api/config/mcp";
 const remoteAgentURL = "/api/agent_prompt";
 const localTokenURL = "/authorization/token";
 
-export async function getLocalToken() {
+type HostServerAuth = { mode: "secret"; secret: string } | { mode: "token_endpoint" };
+
+// The secret is generated by the app for each host_server launch and changes on restart.
+// host_server versions that do not read it from stdin still hand out their own token.
+export async function getLocalToken(): Promise<string> {
+  const auth = await invoke<HostServerAuth>("get_host_server_secret");
+  if (auth.mode === "secret") {
+    return auth.secret;
+  }
+
   const baseURL = getLocalBaseDomain();
   const headers = await getHeaders({ aiden: true });
   const result = await fetchNoProxy(`${baseURL}${localTokenURL}`, {
@@ -16,11 +26,11 @@ export async function getLocalToken() {
   });
 
   if (result.status !== 200) {
-    throw new Error("search status failed: " + result.statusText);
+    throw new Error("get token failed: " + result.statusText);
   }
 
-  const jsonResult = await result.json();
-  return jsonResult;
+  const { data } = (await result.json()) as { data: string };
+  return data;
 }
 
 export async function getRemoteMcpItems() {
@@ -145,6 +145,16 @@ export const useAgentStore = createPersistStore(
         set({ config: newConfig });
         updateConfig(newConfig);
       },
+      deleteInvalidAgent: (index: number) => {
+        const config = get().config;
+        if (!config?.invalid) return;
+        const newConfig = {
+          ...config,
+          invalid: config.invalid.filter((_, i) => i !== index),
+        };
+        set({ config: newConfig });
+        updateConfig(newConfig);
+      },
       handleModel: () => {
         // 针对后端删除了 agent 所绑定的模型
         if (!get()._hasInitialized) return;
@@ -35,6 +35,7 @@ const config = getClientConfig();
 export const DEFAULT_CONFIG = {
   localToken: "",
   hostServerPort: 6888,
+  hostServerUrl: "",
   debugMode: false,
   lastUpdate: Date.now(), // timestamp, to merge state
   submitKey: SubmitKey.Enter,
@@ -221,6 +222,11 @@ export const useAppConfig = createPersistStore(
           hostServerPort: port,
         }));
       },
+      setHostServerUrl(url: string) {
+        set(() => ({
+          hostServerUrl: url,
+        }));
+      },
       setLocalToken(token: string) {
         set(() => ({
           localToken: token,
@@ -12,7 +12,7 @@ import {
   TTemplateInfo,
 } from "@/app/typing";
 import {
-  getFirstValue,
+  getRemoteServer,
   fetchMcpStatus,
   batchFetchMcpStatus,
   getMcpStatusList,
@@ -195,7 +195,8 @@ export const useMcpStore = createPersistStore(
           };
         } else {
           const remoteInfo = mcpRemoteInfoMap.get(id);
-          const remoteItem = getFirstValue(remoteInfo?.basic_config) || {};
+          const remoteItem = getRemoteServer(remoteInfo);
+          if (!remoteItem) return;
           previousConfig = {
             ...remoteItem,
             aiden_mcp_version: remoteInfo?.current_version,
@@ -346,13 +347,14 @@ export const useMcpStore = createPersistStore(
           templateInfo = parseTemplate(config.mcpServers[name]);
         } else {
           // enable -> add item "remote"
+          const remoteItem = getRemoteServer(mcpRemoteInfoMap.get(id));
+          if (!remoteItem) return;
           newConfig = {
             ...config,
             mcpServers: {
               ...config.mcpServers,
               [name]: {
-                ...(getFirstValue(mcpRemoteInfoMap.get(id)?.basic_config) ||
-                  {}),
+                ...remoteItem,
                 aiden_enable: enable,
                 aiden_type: type,
                 aiden_id: id,
@@ -400,8 +402,8 @@ export const useMcpStore = createPersistStore(
           _get();
         if (!config || !config?.mcpServers[name]) return;
         const localItem = config.mcpServers[name];
-        const remoteItem =
-          getFirstValue(mcpRemoteInfoMap.get(id)?.basic_config) || {};
+        const remoteItem = getRemoteServer(mcpRemoteInfoMap.get(id));
+        if (!remoteItem) return;
         const newArgsEnv = updateMcpArgsEnvs(localItem, remoteItem);
         const newConfig = {
           ...config,
@@ -464,6 +466,21 @@ export const useMcpStore = createPersistStore(
         }
       },
 
+      removeInvalidMcpItem: async (name: string) => {
+        console.log("[Mcp store] removeInvalidMcpItem: ", name);
+        const { config } = get();
+        if (!config) return;
+        const invalid = { ...config.invalid };
+        delete invalid[name];
+        const newConfig = {
+          ...config,
+          invalid,
+          errors: config.errors?.filter((error) => error.server !== name),
+        };
+        await updateConfig(newConfig);
+        set({ config: newConfig });
+      },
+
       pollMcpStatus: async () => {
         const { mcpStatusList, batchUpdateMcpStatusList, config } = _get();
         if (!config) return;
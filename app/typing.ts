@@ -149,10 +149,21 @@ export type A2AConfig = {
   a2aServers: A2AServer[];
 };
 
+export type McpFieldError = {
+  server: string | null;
+  field: string;
+  message: string;
+};
+
 export type MCPConfig = {
   version: string;
   mcpServers: Record<string, CustomMCPServer>;
   a2aServers: A2AConfig;
+  // Servers skipped by read_mcp_config because of these errors
+  errors?: McpFieldError[];
+  // The skipped servers as written in the file. They are written back on save
+  // unless removed from here.
+  invalid?: Record<string, unknown>;
 };
 
 export enum McpStepsAction {
@@ -471,6 +482,9 @@ export interface UploadedFile {
 export type AgentConfig = {
   version: string;
   agents: CustomAgents[];
+  // Agents skipped by read_agent_config because they are invalid, as written in
+  // the file. They are written back on save unless removed from here.
+  invalid?: Record<string, unknown>[];
 };
 
 export type DefaultAgent = {
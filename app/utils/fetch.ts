@@ -20,9 +20,7 @@ export const initLocalToken = async () => {
   async function getToken() {
     try {
       const token = await getLocalToken();
-      const { data } = token;
-      console.log("getLocalToken result", data);
-      config.setLocalToken(data);
+      config.setLocalToken(token);
       console.log("getLocalToken success");
     } catch (error) {
       console.log("getLocalToken error", JSON.stringify(error));
@@ -31,9 +29,10 @@ export const initLocalToken = async () => {
   await getToken();
 };
 
+// Full base URL from the host_server_ready event; an external host_server may not be local
 export const getLocalBaseDomain = () => {
-  const hostServerPort = useAppConfig.getState().hostServerPort;
-  return `http://127.0.0.1:${hostServerPort}`;
+  const { hostServerUrl, hostServerPort } = useAppConfig.getState();
+  return hostServerUrl || `http://127.0.0.1:${hostServerPort}`;
 };
 
 export const getBaseChatUrl = () => {
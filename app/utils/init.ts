@@ -1,4 +1,4 @@
-import { initLocalToken } from "../utils/fetch";
+import { getLocalBaseDomain, initLocalToken } from "../utils/fetch";
 import { useMcpStore } from "../store/mcp";
 import { useSettingStore } from "../store/setting";
 import { useAppConfig } from "../store/config";
@@ -7,29 +7,34 @@ import { useAgentStore } from "../store";
 import { track } from "../utils/analysis";
 
 let websocketInitialized = false;
+let wsWorker: Worker | null = null;
 
-const initWebsocketWorker = async () => {
-  console.log("[Main][Websocket] init websocket worker");
-  if (websocketInitialized) {
-    console.warn("[Main][Websocket] WebSocket already initialized, skipping.");
-    return;
-  }
-  const wsWorker = new Worker(new URL("../ws.worker.ts", import.meta.url), {
-    type: "module",
-  });
-
-  const port = useAppConfig.getState().hostServerPort;
+const connectWebsocket = () => {
+  const url = getLocalBaseDomain();
   const localToken = useAppConfig.getState().localToken;
   const userToken = useAuthStore.getState().userToken;
 
-  wsWorker.postMessage({
+  wsWorker?.postMessage({
     type: "connect",
     payload: {
-      port: port,
+      url: url,
       localToken: localToken,
       accessToken: userToken.accessToken,
     },
   });
+};
+
+const initWebsocketWorker = async () => {
+  console.log("[Main][Websocket] init websocket worker");
+  if (websocketInitialized) {
+    console.warn("[Main][Websocket] WebSocket already initialized, skipping.");
+    return;
+  }
+  wsWorker = new Worker(new URL("../ws.worker.ts", import.meta.url), {
+    type: "module",
+  });
+
+  connectWebsocket();
 
   wsWorker.onmessage = (e) => {
     const { type, payload, message } = e.data;
@@ -50,6 +55,12 @@ const initWebsocketWorker = async () => {
   };
 };
 
+// host_server restarted: it has a new secret (and maybe a new port)
+export const hostServerRestarted = async () => {
+  await initLocalToken();
+  connectWebsocket();
+};
+
 export const appDataInit = async () => {
   await initLocalToken();
   useMcpStore.getState().init();
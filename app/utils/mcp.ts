@@ -281,6 +281,16 @@ export const getFirstValue = <T extends Record<string, any>>(
   return firstKey ? obj[firstKey] : undefined;
 };
 
+// A remote server is written from the catalog's basic_config. Without it there is
+// no command or url, and write_mcp_config rejects the entry.
+export const getRemoteServer = (remoteInfo?: TRemoteMcpInfo) => {
+  const server = getFirstValue(remoteInfo?.basic_config || {});
+  if (!server) {
+    toast.error("MCP server details are not loaded yet, please try again.");
+  }
+  return server;
+};
+
 export const getMcpStatusList = async (config: MCPConfig) => {
   if (!config) return;
   const enableList = Object.keys(config.mcpServers || {}).filter((name) => {
@@ -299,6 +309,16 @@ export const getMcpStatusList = async (config: MCPConfig) => {
 export const readMcpConfig = async () => {
   console.log("[Mcp store] readMcpConfig");
   const data = await invoke<MCPConfig>("read_mcp_config");
+  // Invalid servers are skipped by the backend and kept in the file until fixed
+  if (data.errors?.length) {
+    console.warn("[Mcp store] skipped invalid servers", data.errors);
+    toast.error(
+      "Some MCP servers are invalid and were skipped: " +
+        data.errors
+          .map((e) => `${e.server ?? ""} ${e.field}: ${e.message}`)
+          .join("; "),
+    );
+  }
   return data;
 };
 
@@ -431,7 +451,7 @@ export const updateConfig = async (newConfig: any) => {
     }
   } catch (e: any) {
     console.log("Failed to update config", e);
-    toast.error("Failed to update config. " + e);
+    toast.error("Failed to update config. " + (e?.message ?? e));
     throw new Error(e);
   }
 };
@@ -88,7 +88,8 @@ function setupWorkerLogger() {
 setupWorkerLogger();
 
 let socket: WebSocket | null = null;
-let port = 0;
+// host_server base URL with the ws/wss scheme, e.g. ws://127.0.0.1:6888
+let baseUrl = "";
 let localToken = "";
 let retryCount = 0;
 let accessToken = "";
@@ -102,8 +103,6 @@ const pongTimeout = 3000;
 let pingTimer: ReturnType<typeof setInterval> | null = null;
 let pongTimer: ReturnType<typeof setTimeout> | null = null;
 
-const baseUrl = "ws://localhost";
-
 function connect() {
   if (
     socket &&
@@ -118,7 +117,7 @@ function connect() {
     return;
   }
 
-  const url = `${baseUrl}:${port}/ws?token=${localToken}`;
+  const url = `${baseUrl}/ws?token=${localToken}`;
   socket = new WebSocket(url);
 
   socket.onopen = () => {
@@ -215,9 +214,10 @@ onmessage = (e: MessageEvent) => {
   const { type, payload } = e.data;
   switch (type) {
     case "connect":
-      port = payload.port;
+      baseUrl = payload.url.replace(/^http/, "ws");
       localToken = payload.localToken;
       accessToken = payload.accessToken;
+      retryCount = 0;
       connect();
       break;
 
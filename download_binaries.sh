@@ -94,6 +94,9 @@ else
     exit 1
 fi
 
+# 生成校验清单（sha256 + 版本），应用启动 host_server 前会据此校验
+bash .github/scripts/host-server-manifest.sh write "src-tauri/resources/$ASSET_NAME"
+
 echo "拷贝 $ASSET_NAME 到 node_modules目录，用于本地调试 "
 rm -rf node_modules/$ASSET_NAME
 cp -r src-tauri/resources/$ASSET_NAME node_modules/$ASSET_NAME
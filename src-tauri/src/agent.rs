@@ -1,9 +1,11 @@
-use semver::Version;
+use crate::config::{self, ConfigKind};
+use crate::config_history::{self, SnapshotReason};
+use crate::host::Host;
+use crate::schema::{self, Diagnostic, JsonSchema};
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{json, Value};
 
-use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tauri::{api::path::app_data_dir, AppHandle, Config};
 
 pub fn get_user_config_path(config: &Config) -> Option<PathBuf> {
@@ -23,6 +25,221 @@ pub struct AgentConfig {
     pub version: String,
     pub syncVersion: String,
     pub agents: Vec<serde_json::Value>,
+    /// 校验失败、读取时跳过的 agent 原文，随 read_agent_config 返回给前端显示与删除；不写入文件
+    ///
+    /// 保存时只写回这里仍保留的条目；为 None（旧前端没有传）时保留文件中现有的全部无效 agent。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub invalid: Option<Vec<serde_json::Value>>,
+}
+
+/// agents 中每项的已知字段：(名称, JSON 类型, 是否必填)；schema 与校验共用这张表
+const AGENT_FIELDS: &[(&str, &str, bool)] = &[
+    ("agent_id", "string", true),
+    ("agent_name", "string", true),
+    ("avatar", "string", false),
+    ("source", "string", false),
+    ("description", "string", false),
+    ("prompt", "string", false),
+    ("enabled", "boolean", false),
+    ("agent_type", "string", false),
+    ("model_name", "string", false),
+    ("model_provider", "string", false),
+    ("endpoint", "string", false),
+    ("api_key", "string", false),
+];
+
+/// builtIn 为官方内置默认开启，default 为官方内置可选，custom 为用户添加
+const AGENT_SOURCES: &[&str] = &["builtIn", "default", "custom"];
+
+const AGENT_TYPES: &[&str] = &["text", "multi-model"];
+
+fn agent_schema() -> Value {
+    let properties = AGENT_FIELDS
+        .iter()
+        .map(|(name, kind, _)| {
+            let field = match *name {
+                "source" => schema::string_enum(AGENT_SOURCES),
+                "agent_type" => schema::string_enum(AGENT_TYPES),
+                "agent_id" => json!({ "type": "string", "minLength": 1 }),
+                _ => json!({ "type": kind }),
+            };
+            (*name, field)
+        })
+        .collect();
+    let required: Vec<&str> = AGENT_FIELDS
+        .iter()
+        .filter(|(_, _, required)| *required)
+        .map(|(name, _, _)| *name)
+        .collect();
+    schema::object(properties, &required)
+}
+
+impl JsonSchema for AgentConfig {
+    fn json_schema() -> Value {
+        schema::object(
+            vec![
+                ("version", String::json_schema()),
+                ("syncVersion", String::json_schema()),
+                ("agents", json!({ "type": "array", "items": agent_schema() })),
+            ],
+            &["version", "syncVersion", "agents"],
+        )
+    }
+}
+
+fn json_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// 按 AGENT_FIELDS 校验；agent_id 重复会让前端无法区分 agent，视为错误
+pub fn validate_agent_config(value: &Value) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let object = match value.as_object() {
+        Some(object) => object,
+        None => return vec![Diagnostic::error("", "must be an object")],
+    };
+    for field in ["version", "syncVersion"] {
+        if !object.get(field).map_or(false, Value::is_string) {
+            diagnostics.push(Diagnostic::error(field, "must be a string"));
+        }
+    }
+    let agents = match object.get("agents") {
+        Some(Value::Array(agents)) => agents,
+        _ => {
+            diagnostics.push(Diagnostic::error("agents", "must be an array"));
+            return diagnostics;
+        }
+    };
+
+    let mut seen: Vec<&str> = Vec::new();
+    for (i, agent) in agents.iter().enumerate() {
+        diagnostics.extend(validate_agent(i, agent, &mut seen));
+    }
+    diagnostics
+}
+
+/// 校验 agents 中的一项；seen 为前面已出现的 agent_id
+fn validate_agent<'a>(i: usize, agent: &'a Value, seen: &mut Vec<&'a str>) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let path = format!("agents[{}]", i);
+    let agent = match agent.as_object() {
+        Some(agent) => agent,
+        None => return vec![Diagnostic::error(path, "must be an object")],
+    };
+    for (name, kind, required) in AGENT_FIELDS {
+        let field_path = format!("{}.{}", path, name);
+        match agent.get(*name) {
+            None | Some(Value::Null) if *required => {
+                diagnostics.push(Diagnostic::error(field_path, "is required"))
+            }
+            None | Some(Value::Null) => {}
+            Some(value) if json_type(value) != *kind => {
+                diagnostics.push(Diagnostic::error(field_path, format!("must be a {}", kind)))
+            }
+            Some(_) => {}
+        }
+    }
+    if let Some(id) = agent.get("agent_id").and_then(Value::as_str) {
+        if id.is_empty() {
+            diagnostics.push(Diagnostic::error(format!("{}.agent_id", path), "is required"));
+        } else if seen.contains(&id) {
+            diagnostics.push(Diagnostic::error(
+                format!("{}.agent_id", path),
+                format!("duplicate agent_id {}", id),
+            ));
+        }
+        seen.push(id);
+    }
+    for (field, allowed) in [("source", AGENT_SOURCES), ("agent_type", AGENT_TYPES)] {
+        if let Some(value) = agent.get(field).and_then(Value::as_str) {
+            if !allowed.contains(&value) {
+                diagnostics.push(Diagnostic::warning(
+                    format!("{}.{}", path, field),
+                    format!("unknown value {:?}, expected one of {}", value, allowed.join(", ")),
+                ));
+            }
+        }
+    }
+    diagnostics
+}
+
+/// 有错误的 agent 的下标
+fn invalid_agents(agents: &[Value]) -> Vec<usize> {
+    let mut seen = Vec::new();
+    let mut invalid = Vec::new();
+    for (i, agent) in agents.iter().enumerate() {
+        if schema::has_errors(&validate_agent(i, agent, &mut seen)) {
+            invalid.push(i);
+        }
+    }
+    invalid
+}
+
+fn agent_id(agent: &Value) -> Option<&str> {
+    agent.get("agent_id").and_then(Value::as_str)
+}
+
+/// 读取配置，校验出错的 agent 移到 invalid
+pub fn load_agent_config(path: &Path) -> Result<AgentConfig, String> {
+  let value = config::read_config_file(path)?;
+  let mut config: AgentConfig = serde_json::from_value(value).map_err(|e| e.to_string())?;
+  let invalid = invalid_agents(&config.agents);
+  if !invalid.is_empty() {
+    log::warn!(
+      "Skipped {} invalid agent(s) in {}, run `aiden config validate` for details",
+      invalid.len(),
+      path.display()
+    );
+  }
+  let (skipped, agents): (Vec<_>, Vec<_>) = config
+      .agents
+      .into_iter()
+      .enumerate()
+      .partition(|(i, _)| invalid.contains(i));
+  config.agents = agents.into_iter().map(|(_, agent)| agent).collect();
+  config.invalid = Some(skipped.into_iter().map(|(_, agent)| agent).collect());
+  Ok(config)
+}
+
+/// 校验后写入；config.invalid 中的无效 agent 追加回去，与有效 agent 的 agent_id 重复的以有效的为准
+pub fn save_agent_config(path: &Path, config: &AgentConfig) -> Result<(), String> {
+  let mut merged = config.clone();
+  let invalid = match merged.invalid.take() {
+      Some(invalid) => invalid,
+      None => load_agent_config(path)
+          .ok()
+          .and_then(|current| current.invalid)
+          .unwrap_or_default(),
+  };
+  let json = serde_json::to_value(&merged).map_err(|e| e.to_string())?;
+  config::check(ConfigKind::Agent, &json)?;
+
+  let valid_count = merged.agents.len();
+  let kept: Vec<Value> = invalid
+      .into_iter()
+      .filter(|agent| {
+          agent_id(agent).map_or(true, |id| {
+              !config.agents.iter().any(|valid| agent_id(valid) == Some(id))
+          })
+      })
+      .collect();
+  merged.agents.extend(kept);
+  // 合并后的文档再校验一次：追加回去的条目不能让任何一个有效 agent 在下次读取时被跳过
+  if let Some(i) = invalid_agents(&merged.agents).into_iter().find(|&i| i < valid_count) {
+      return Err(format!(
+          "Invalid agent config: agents[{}] conflicts with an invalid agent kept in the file",
+          i
+      ));
+  }
+  config_history::snapshot(ConfigKind::Agent, path, SnapshotReason::Write);
+  config::write_config_json(path, &merged)
 }
 
 /// 读取配置
@@ -30,142 +247,124 @@ pub struct AgentConfig {
 pub fn read_agent_config(app: AppHandle) -> Result<AgentConfig, String> {
   let path = get_user_config_path_from_app(&app).ok_or("配置路径不存在")?;
   log::info!("测试, 读取的文件路径为={}", path.display());
-  let contents = fs::read_to_string(&path).map_err(|e| e.to_string())?;
-  let config: AgentConfig = serde_json::from_str(&contents).map_err(|e| e.to_string())?;
-  Ok(config)
+  load_agent_config(&path)
 }
 
 /// 写入配置
 #[tauri::command]
 pub fn write_agent_config(app: AppHandle, new_config: AgentConfig) -> Result<(), String> {
     let path = get_user_config_path_from_app(&app).ok_or("配置路径不存在")?;
-    let json_str = serde_json::to_string_pretty(&new_config).map_err(|e| e.to_string())?;
-    fs::write(&path, json_str).map_err(|e| e.to_string())?;
-    Ok(())
+    save_agent_config(&path, &new_config)
 }
 
-pub fn init_agent_config(app: &tauri::App) -> Result<(), String> {
-  let config = app.config();
-  let user_config_path =
-      get_user_config_path(&config).ok_or("Failed to get Agent config file path.")?;
-
-  let default_path: PathBuf = app
-      .path_resolver()
-      .resolve_resource("resources/agent.config.json")
-      .ok_or("Cannot find default Agent config in resources.")?;
-
-  // 首次安装，用户 config 不存在
-  if !user_config_path.exists() {
-      fs::copy(&default_path, &user_config_path)
-          .map_err(|e| format!("Copy Agent config failed: {}", e))?;
-      log::info!("Agent config initialized: {:?}", user_config_path);
-      return Ok(());
-  }
+pub fn init_agent_config<H: Host>(app: &H) -> Result<(), String> {
+  config::init_config(app, ConfigKind::Agent)
+}
 
-  // 读取默认 config
-  let default_text = fs::read_to_string(&default_path)
-      .map_err(|e| format!("Failed to read default Agent config: {}", e))?;
-  let default_json: Value = serde_json::from_str(&default_text)
-      .map_err(|e| format!("Invalid JSON in default config: {}", e))?;
-
-  // 读取用户 config
-  let user_text = fs::read_to_string(&user_config_path)
-      .map_err(|e| format!("Failed to read user Agent config: {}", e))?;
-  let mut user_json: Value = serde_json::from_str(&user_text)
-      .map_err(|e: serde_json::Error| format!("Invalid JSON in user config: {}", e))?;
-
-  // ========= Step 1: 强制 syncVersion 同步判断 ============
-  let default_sync_str = default_json
-      .get("syncVersion")
-      .and_then(|v| v.as_str())
-      .unwrap_or("0.0.0");
-  let user_sync_str = user_json
-      .get("syncVersion")
-      .and_then(|v| v.as_str())
-      .unwrap_or("0.0.0");
-
-  let default_sync = Version::parse(default_sync_str).unwrap_or_else(|_| Version::new(0, 0, 0));
-  let user_sync = Version::parse(user_sync_str).unwrap_or_else(|_| Version::new(0, 0, 0));
-
-  log::info!(
-      "Agent config syncVersion: default={}, user={}",
-      default_sync,
-      user_sync
-  );
-
-  if default_sync > user_sync {
-      fs::copy(&default_path, &user_config_path)
-          .map_err(|e| format!("Forced sync copy failed: {}", e))?;
-      log::info!(
-          "Agent config forcibly synced due to syncVersion mismatch: {} -> {}",
-          user_sync,
-          default_sync
-      );
-      return Ok(());
-  }
+/// 升级时保留用户自己的 agent，builtIn agent 以默认配置为准
+pub fn merge_builtin_agents(user_json: &mut Value, default_json: &Value) {
+  let empty_array: Vec<Value> = Vec::new();
+  let default_agents = default_json
+      .get("agents")
+      .and_then(|v| v.as_array())
+      .unwrap_or(&empty_array);
+
+  let mut user_agents = user_json
+      .get("agents")
+      .and_then(|v| v.as_array().cloned())
+      .unwrap_or_else(Vec::new);
 
-  // ========= Step 2: 正常 version 增量更新逻辑 ============
-  let default_version_str = default_json
-      .get("version")
-      .and_then(|v| v.as_str())
-      .unwrap_or("");
-  let user_version_str = user_json
-      .get("version")
-      .and_then(|v: &Value| v.as_str())
-      .unwrap_or("");
-
-  log::info!(
-      "MCP config version: default={}, user={}",
-      default_version_str,
-      user_version_str
-  );
-
-  let default_version =
-      Version::parse(default_version_str).unwrap_or_else(|_| Version::new(0, 0, 0));
-  let user_version = Version::parse(user_version_str).unwrap_or_else(|_| Version::new(0, 0, 0));
-
-  if default_version > user_version {
-      log::info!(
-          "Agent config update needed: {} -> {}",
-          user_version,
-          default_version
-      );
-
-      let empty_array: Vec<Value> = Vec::new();
-      let default_agents = default_json
-            .get("agents")
-            .and_then(|v| v.as_array())
-            .unwrap_or(&empty_array);
-
-      
-      let mut user_agents = user_json
-          .get("agents")
-          .and_then(|v| v.as_array().cloned())
-          .unwrap_or_else(Vec::new);
-      
-      // 过滤掉用户配置中所有 builtIn 类型的 agent
-      user_agents.retain(|agent| {
-          agent.get("source").map_or(true, |s| s != "builtIn")
-      });
-
-      // 添加默认配置中的所有 builtIn agent
-      for default_agent in default_agents {
-          if default_agent.get("source") == Some(&Value::String("builtIn".into())) {
-              user_agents.push(default_agent.clone());
-          }
+  // 过滤掉用户配置中所有 builtIn 类型的 agent
+  user_agents.retain(|agent| {
+      agent.get("source").map_or(true, |s| s != "builtIn")
+  });
+
+  // 添加默认配置中的所有 builtIn agent
+  for default_agent in default_agents {
+      if default_agent.get("source") == Some(&Value::String("builtIn".into())) {
+          user_agents.push(default_agent.clone());
       }
+  }
 
-      user_json["agents"] = Value::Array(user_agents);
-      user_json["version"] = Value::String(default_version.to_string());
+  user_json["agents"] = Value::Array(user_agents);
+}
 
-      fs::write(
-          &user_config_path,
-          serde_json::to_string_pretty(&user_json).unwrap(),
-      )
-      .map_err(|e| format!("Failed to write updated Agent config: {}", e))?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::Severity;
 
-      log::info!("Agent config upgraded successfully.");
-  }
+    fn agent(id: &str) -> Value {
+        json!({ "agent_id": id, "agent_name": "Writer" })
+    }
+
+    fn temp_config(name: &str, agents: Vec<Value>) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("aiden-agent-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("agent.config.json");
+        let config = json!({ "version": "1.0.0", "syncVersion": "1.0.0", "agents": agents });
+        std::fs::write(&path, config.to_string()).unwrap();
+        path
+    }
+
+    fn saved_agents(path: &Path) -> Vec<Value> {
+        let saved: Value = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        assert!(saved.get("invalid").is_none());
+        saved["agents"].as_array().unwrap().clone()
+    }
+
+    #[test]
+    fn invalid_agents_are_kept_until_removed() {
+        let broken = json!({ "agent_id": "broken" });
+        let path = temp_config("kept", vec![agent("writer"), broken.clone()]);
+
+        let config = load_agent_config(&path).unwrap();
+        assert_eq!(config.agents, [agent("writer")]);
+        assert_eq!(config.invalid, Some(vec![broken.clone()]));
+
+        save_agent_config(&path, &config).unwrap();
+        assert_eq!(saved_agents(&path), [agent("writer"), broken.clone()]);
+
+        // 没有传 invalid 时保留文件中现有的
+        let mut config = load_agent_config(&path).unwrap();
+        config.invalid = None;
+        save_agent_config(&path, &config).unwrap();
+        assert_eq!(saved_agents(&path), [agent("writer"), broken]);
+
+        let mut config = load_agent_config(&path).unwrap();
+        config.invalid = Some(Vec::new());
+        save_agent_config(&path, &config).unwrap();
+        assert_eq!(saved_agents(&path), [agent("writer")]);
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn invalid_agent_with_the_same_id_is_replaced() {
+        let path = temp_config("same-id", vec![json!({ "agent_id": "writer" })]);
+
+        let mut config = load_agent_config(&path).unwrap();
+        assert!(config.agents.is_empty());
+        config.agents.push(agent("writer"));
+        save_agent_config(&path, &config).unwrap();
+        assert_eq!(saved_agents(&path), [agent("writer")]);
+        assert_eq!(load_agent_config(&path).unwrap().agents, [agent("writer")]);
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
 
-  Ok(())
+    #[test]
+    fn duplicate_agent_id_is_an_error() {
+        let config = json!({
+            "version": "1.0.0",
+            "syncVersion": "1.0.0",
+            "agents": [agent("writer"), agent("coder"), agent("writer")],
+        });
+        let errors: Vec<String> = validate_agent_config(&config)
+            .into_iter()
+            .filter(|d| d.severity == Severity::Error)
+            .map(|d| d.path)
+            .collect();
+        assert_eq!(errors, ["agents[2].agent_id"]);
+        assert_eq!(invalid_agents(config["agents"].as_array().unwrap()), [2]);
+    }
 }
@@ -1,3 +1,7 @@
+#[cfg(target_os = "linux")]
+use crate::constants::HOST_SERVER_SHUTDOWN_GRACE;
+#[cfg(target_os = "linux")]
+use crate::{process, run_state};
 use std::fs;
 use tauri::api::path::app_data_dir;
 use tauri::Config;
@@ -30,3 +34,59 @@ pub fn cleanup_database(config: &Config) {
         }
     }
 }
+
+/// 清理上一次运行崩溃后遗留的 host_server 及 MCP server 进程，它们占用的端口随之释放
+///
+/// 只处理 run_state 中记录过、且 start_time 与命令行都一致的进程；host_server 组长仍是原进程时，
+/// 再加上它进程组内的成员。组长已退出时 pgid 可能已被复用，不按进程组清理。
+///
+/// 只在 Linux 上可用：其他平台没有进程记录（见 process::ProcessInfo）。
+#[cfg(target_os = "linux")]
+pub fn sweep_orphans(config: &Config) {
+    let mut state = match run_state::load(config) {
+        Some(state) => state,
+        None => return,
+    };
+
+    if let Some(app) = &state.app {
+        if app.pid != std::process::id() && process::is_same_process(app) {
+            log::info!(
+                "Previous Aiden instance (pid {}) is still running, skipping orphan sweep.",
+                app.pid
+            );
+            return;
+        }
+    }
+
+    let mut orphans: Vec<process::ProcessInfo> = state
+        .processes
+        .iter()
+        .filter(|p| process::matches_record(p))
+        .cloned()
+        .collect();
+
+    if let Some(host_server) = &state.host_server {
+        // 只有组长确认是上次启动的 host_server，这个进程组才属于我们
+        if process::matches_record(host_server) {
+            orphans.push(host_server.clone());
+            for member in process::all_processes() {
+                if member.pgid == host_server.pgid && !orphans.iter().any(|p| p.pid == member.pid) {
+                    orphans.push(member);
+                }
+            }
+        }
+    }
+
+    if orphans.is_empty() {
+        log::info!("No orphaned processes from the previous session.");
+    } else {
+        log::warn!(
+            "Found {} orphaned process(es) from the previous session, cleaning up.",
+            orphans.len()
+        );
+        process::kill_processes("orphan", &orphans, HOST_SERVER_SHUTDOWN_GRACE);
+    }
+    state.host_server = None;
+    state.processes.clear();
+    run_state::save(config, &state);
+}
@@ -0,0 +1,733 @@
+use crate::agent::{self, AgentConfig};
+use crate::config::{self, ConfigKind};
+use crate::headless::Headless;
+use crate::host::Host;
+use crate::mcp::{self, MCPConfig};
+use crate::mcp_server::{self, AidenType, McpServer, McpTransport};
+use crate::schema::{self, Diagnostic};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tauri::{Config, PackageInfo};
+
+#[cfg(windows)]
+extern "system" {
+    fn AttachConsole(process_id: u32) -> i32;
+}
+
+/// release 版在 Windows 上是 GUI 程序（windows_subsystem = "windows"），没有控制台，
+/// 输出会被丢弃；从终端启动时挂到父进程的控制台上，println / eprintln 才能看到
+pub fn attach_parent_console() {
+    #[cfg(windows)]
+    {
+        const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+        // 从资源管理器启动时没有父控制台，失败即保持原样
+        unsafe {
+            AttachConsole(ATTACH_PARENT_PROCESS);
+        }
+    }
+}
+
+const USAGE: &str = "\
+Usage:
+  aiden mcp list
+  aiden mcp show <name>
+  aiden mcp add <name> [--env KEY=VALUE]... -- <command> [args]...
+  aiden mcp add <name> --url <url> [--transport sse|streamable_http]
+  aiden mcp remove|enable|disable <name>
+  aiden agent list
+  aiden agent add <id> --name <name> [--type text] [--model <model>] [--provider <provider>]
+                  [--endpoint <url>] [--api-key <key>] [--description <text>] [--prompt <text>]
+  aiden agent remove <id>
+  aiden agent set <id> <field> <value>
+  aiden config path
+  aiden config validate
+  aiden config schema mcp|agent
+  aiden config upgrade [--dry-run]
+
+Add --json to any command for machine-readable output.";
+
+/// 不带值的选项，其余 `--name` 都需要一个值
+const SWITCHES: &[&str] = &["json", "dry-run"];
+
+#[derive(Debug)]
+enum CliError {
+    /// 参数错误，退出码 2 并打印用法
+    Usage(String),
+    /// 执行失败，退出码 1
+    Failed(String),
+}
+
+impl From<String> for CliError {
+    fn from(message: String) -> Self {
+        CliError::Failed(message)
+    }
+}
+
+type CliResult = Result<(), CliError>;
+
+/// 手写的参数解析：位置参数、`--name value` / `--name=value` 选项，`--` 之后原样保留
+struct Args {
+    words: Vec<String>,
+    options: BTreeMap<String, Vec<String>>,
+    switches: Vec<String>,
+    trailing: Vec<String>,
+}
+
+impl Args {
+    fn parse(raw: Vec<String>) -> Result<Args, CliError> {
+        let mut args = Args {
+            words: Vec::new(),
+            options: BTreeMap::new(),
+            switches: Vec::new(),
+            trailing: Vec::new(),
+        };
+        let mut iter = raw.into_iter();
+        while let Some(arg) = iter.next() {
+            if arg == "--" {
+                args.trailing = iter.collect();
+                break;
+            }
+            let name = match arg.strip_prefix("--") {
+                Some(name) => name.to_string(),
+                None => {
+                    args.words.push(arg);
+                    continue;
+                }
+            };
+            if SWITCHES.contains(&name.as_str()) {
+                args.switches.push(name);
+                continue;
+            }
+            let (name, value) = match name.split_once('=') {
+                Some((name, value)) => (name.to_string(), value.to_string()),
+                None => {
+                    let value = iter
+                        .next()
+                        .ok_or_else(|| CliError::Usage(format!("--{} needs a value", name)))?;
+                    (name, value)
+                }
+            };
+            args.options.entry(name).or_default().push(value);
+        }
+        Ok(args)
+    }
+
+    fn switch(&self, name: &str) -> bool {
+        self.switches.iter().any(|s| s == name)
+    }
+
+    fn option(&self, name: &str) -> Option<&str> {
+        self.options
+            .get(name)
+            .and_then(|values| values.last())
+            .map(String::as_str)
+    }
+
+    fn take_option(&mut self, name: &str) -> Option<String> {
+        self.options.remove(name).and_then(|mut values| values.pop())
+    }
+
+    /// 第 index 个位置参数（0 为 mcp / agent / config）
+    fn word(&self, index: usize, what: &str) -> Result<&str, CliError> {
+        self.words
+            .get(index)
+            .map(String::as_str)
+            .ok_or_else(|| CliError::Usage(format!("Missing {}", what)))
+    }
+
+    /// 拒绝当前命令不支持的选项，避免拼错的选项被静默忽略
+    fn reject_unknown(&self, allowed: &[&str]) -> CliResult {
+        match self.options.keys().find(|name| !allowed.contains(&name.as_str())) {
+            Some(name) => Err(CliError::Usage(format!("Unknown option --{}", name))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// 按 --json 输出 JSON，否则输出人类可读文本
+///
+/// 写 stdout 失败（如管道被 head 提前关闭）时静默忽略。
+fn print(args: &Args, value: &Value, human: impl FnOnce() -> String) {
+    let text = if args.switch("json") {
+        serde_json::to_string_pretty(value).unwrap_or_default()
+    } else {
+        human()
+    };
+    if !text.is_empty() {
+        let _ = writeln!(std::io::stdout(), "{}", text);
+    }
+}
+
+/// 按列对齐输出表格
+fn table(header: &[&str], rows: Vec<Vec<String>>) -> String {
+    let mut widths: Vec<usize> = header.iter().map(|h| h.len()).collect();
+    for row in &rows {
+        for (i, cell) in row.iter().enumerate() {
+            widths[i] = widths[i].max(cell.chars().count());
+        }
+    }
+    let line = |cells: Vec<String>| {
+        cells
+            .iter()
+            .enumerate()
+            .map(|(i, cell)| format!("{:width$}", cell, width = widths[i]))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+    let mut lines = vec![line(header.iter().map(|h| h.to_string()).collect())];
+    lines.extend(rows.into_iter().map(line));
+    lines.join("\n")
+}
+
+fn user_path(host: &Headless, kind: ConfigKind) -> Result<PathBuf, CliError> {
+    kind.user_path(&host.app_config())
+        .ok_or_else(|| CliError::Failed(format!("Failed to get {} config file path.", kind.label())))
+}
+
+fn str_field<'a>(value: &'a Value, field: &str) -> &'a str {
+    value.get(field).and_then(Value::as_str).unwrap_or_default()
+}
+
+// ========= mcp ============
+
+fn server_summary(name: &str, server: &McpServer) -> Value {
+    let (command, args, url) = match &server.transport {
+        McpTransport::Stdio { command, args, .. } => (Some(command), Some(args), None),
+        McpTransport::Sse { url, .. } | McpTransport::StreamableHttp { url, .. } => {
+            (None, None, Some(url))
+        }
+    };
+    json!({
+        "name": name,
+        "enabled": server.enabled(),
+        "type": server.aiden.aiden_type.map(|t| json!(t)).unwrap_or_else(|| json!("custom")),
+        "transport": server.transport.name(),
+        "command": command,
+        "args": args,
+        "url": url,
+    })
+}
+
+fn server_target(server: &McpServer) -> String {
+    match &server.transport {
+        McpTransport::Stdio { command, args, .. } => {
+            let mut words = vec![command.clone()];
+            words.extend(args.iter().cloned());
+            words.join(" ")
+        }
+        McpTransport::Sse { url, .. } | McpTransport::StreamableHttp { url, .. } => url.clone(),
+    }
+}
+
+fn mcp_server<'a>(config: &'a mut MCPConfig, name: &str) -> Result<&'a mut McpServer, CliError> {
+    config
+        .mcpServers
+        .get_mut(name)
+        .ok_or_else(|| CliError::Failed(format!("No MCP server named {}", name)))
+}
+
+fn mcp_command(host: &Headless, mut args: Args) -> CliResult {
+    let action = args.word(1, "mcp command")?.to_string();
+    let path = user_path(host, ConfigKind::Mcp)?;
+    let mut config = mcp::load_mcp_config(&path)?;
+    match action.as_str() {
+        "list" => {
+            args.reject_unknown(&[])?;
+            let servers: Vec<Value> = config
+                .mcpServers
+                .iter()
+                .map(|(name, server)| server_summary(name, server))
+                .collect();
+            print(&args, &Value::Array(servers.clone()), || {
+                let rows = config
+                    .mcpServers
+                    .iter()
+                    .zip(&servers)
+                    .map(|((name, server), summary)| {
+                        vec![
+                            name.clone(),
+                            if summary["enabled"] == true { "yes" } else { "no" }.to_string(),
+                            str_field(summary, "type").to_string(),
+                            str_field(summary, "transport").to_string(),
+                            server_target(server),
+                        ]
+                    })
+                    .collect();
+                table(&["NAME", "ENABLED", "TYPE", "TRANSPORT", "TARGET"], rows)
+            });
+            Ok(())
+        }
+        "show" => {
+            args.reject_unknown(&[])?;
+            let name = args.word(2, "server name")?;
+            let server = mcp_server(&mut config, name)?.to_value();
+            print(&args, &server, || {
+                format!(
+                    "{}\n{}",
+                    name,
+                    serde_json::to_string_pretty(&server).unwrap_or_default()
+                )
+            });
+            Ok(())
+        }
+        "add" => {
+            args.reject_unknown(&["env", "url", "transport"])?;
+            let name = args.word(2, "server name")?.to_string();
+            if config.mcpServers.contains_key(&name) {
+                return Err(CliError::Failed(format!("MCP server {} already exists", name)));
+            }
+            let mut server = Map::new();
+            server.insert("aiden_id".into(), json!(name));
+            server.insert("aiden_type".into(), json!("custom"));
+            server.insert("aiden_enable".into(), json!(true));
+            server.insert("aiden_mcp_version".into(), json!(""));
+            match (args.take_option("url"), args.trailing.split_first()) {
+                (Some(url), None) => {
+                    let transport = args.take_option("transport").unwrap_or_else(|| "sse".into());
+                    server.insert("url".into(), json!(url));
+                    server.insert("transport".into(), json!(transport));
+                }
+                (None, Some((command, rest))) => {
+                    server.insert("command".into(), json!(command));
+                    server.insert("args".into(), json!(rest));
+                    server.insert("transport".into(), json!("stdio"));
+                }
+                _ => {
+                    return Err(CliError::Usage(
+                        "Give either --url <url> or -- <command> [args]...".into(),
+                    ))
+                }
+            }
+            if let Some(pairs) = args.options.remove("env") {
+                let mut env = Map::new();
+                for pair in pairs {
+                    let (key, value) = pair
+                        .split_once('=')
+                        .ok_or_else(|| CliError::Usage(format!("--env expects KEY=VALUE, got {}", pair)))?;
+                    env.insert(key.to_string(), json!(value));
+                }
+                server.insert("env".into(), Value::Object(env));
+            }
+            let server = McpServer::from_value(&name, &Value::Object(server))
+                .map_err(|errors| CliError::Failed(mcp_server::describe_errors(&errors)))?;
+            config.mcpServers.insert(name.clone(), server.clone());
+            mcp::save_mcp_config(&path, &config)?;
+            print(&args, &server_summary(&name, &server), || {
+                format!("Added MCP server {}", name)
+            });
+            Ok(())
+        }
+        "remove" => {
+            args.reject_unknown(&[])?;
+            let name = args.word(2, "server name")?.to_string();
+            // 校验失败的 server 也能删除，不必先修好
+            let invalid = config.invalid.remove(&name);
+            let server = config.mcpServers.remove(&name);
+            if server.is_none() && invalid.is_none() {
+                return Err(CliError::Failed(format!("No MCP server named {}", name)));
+            }
+            if server.map_or(false, |server| server.aiden.aiden_type == Some(AidenType::Default)) {
+                eprintln!(
+                    "Note: {} is a default server and will come back on the next config upgrade; use `aiden mcp disable {}` to turn it off",
+                    name, name
+                );
+            }
+            mcp::save_mcp_config(&path, &config)?;
+            print(&args, &json!({ "removed": name }), || {
+                format!("Removed MCP server {}", name)
+            });
+            Ok(())
+        }
+        "enable" | "disable" => {
+            args.reject_unknown(&[])?;
+            let enable = action == "enable";
+            let name = args.word(2, "server name")?.to_string();
+            mcp_server(&mut config, &name)?.aiden.aiden_enable = Some(enable);
+            mcp::save_mcp_config(&path, &config)?;
+            print(&args, &json!({ "name": name, "enabled": enable }), || {
+                format!("{} MCP server {}", if enable { "Enabled" } else { "Disabled" }, name)
+            });
+            Ok(())
+        }
+        other => Err(CliError::Usage(format!("Unknown mcp command: {}", other))),
+    }
+}
+
+// ========= agent ============
+
+fn agent_index(config: &AgentConfig, id: &str) -> Result<usize, CliError> {
+    config
+        .agents
+        .iter()
+        .position(|agent| str_field(agent, "agent_id") == id)
+        .ok_or_else(|| CliError::Failed(format!("No agent with id {}", id)))
+}
+
+fn agent_summary(agent: &Value) -> Value {
+    json!({
+        "agent_id": agent.get("agent_id"),
+        "agent_name": agent.get("agent_name"),
+        "enabled": agent.get("enabled").and_then(Value::as_bool).unwrap_or(true),
+        "source": agent.get("source"),
+        "agent_type": agent.get("agent_type"),
+        "model_name": agent.get("model_name"),
+    })
+}
+
+fn agent_command(host: &Headless, args: Args) -> CliResult {
+    let action = args.word(1, "agent command")?.to_string();
+    let path = user_path(host, ConfigKind::Agent)?;
+    let mut config = agent::load_agent_config(&path)?;
+    match action.as_str() {
+        "list" => {
+            args.reject_unknown(&[])?;
+            let agents: Vec<Value> = config.agents.iter().map(agent_summary).collect();
+            print(&args, &Value::Array(agents.clone()), || {
+                let rows = agents
+                    .iter()
+                    .map(|agent| {
+                        vec![
+                            str_field(agent, "agent_id").to_string(),
+                            str_field(agent, "agent_name").to_string(),
+                            if agent["enabled"] == true { "yes" } else { "no" }.to_string(),
+                            str_field(agent, "source").to_string(),
+                            str_field(agent, "agent_type").to_string(),
+                            str_field(agent, "model_name").to_string(),
+                        ]
+                    })
+                    .collect();
+                table(&["ID", "NAME", "ENABLED", "SOURCE", "TYPE", "MODEL"], rows)
+            });
+            Ok(())
+        }
+        "add" => {
+            args.reject_unknown(&[
+                "name",
+                "type",
+                "model",
+                "provider",
+                "endpoint",
+                "api-key",
+                "description",
+                "prompt",
+                "avatar",
+            ])?;
+            let id = args.word(2, "agent id")?.to_string();
+            if agent_index(&config, &id).is_ok() {
+                return Err(CliError::Failed(format!("Agent {} already exists", id)));
+            }
+            let name = args
+                .option("name")
+                .ok_or_else(|| CliError::Usage("--name is required".into()))?;
+            let agent = json!({
+                "agent_id": id,
+                "agent_name": name,
+                "avatar": args.option("avatar").unwrap_or("🤖"),
+                "source": "custom",
+                "description": args.option("description").unwrap_or_default(),
+                "prompt": args.option("prompt").unwrap_or_default(),
+                "enabled": true,
+                "agent_type": args.option("type").unwrap_or("text"),
+                "model_name": args.option("model").unwrap_or_default(),
+                "model_provider": args.option("provider").unwrap_or("openai"),
+                "endpoint": args.option("endpoint").unwrap_or_default(),
+                "api_key": args.option("api-key").unwrap_or_default(),
+            });
+            config.agents.push(agent.clone());
+            agent::save_agent_config(&path, &config)?;
+            print(&args, &agent_summary(&agent), || format!("Added agent {}", id));
+            Ok(())
+        }
+        "remove" => {
+            args.reject_unknown(&[])?;
+            let id = args.word(2, "agent id")?.to_string();
+            // 校验失败的 agent 也能按 agent_id 删除，不必先修好
+            let invalid = config.invalid.get_or_insert_with(Vec::new);
+            let before = invalid.len();
+            invalid.retain(|agent| str_field(agent, "agent_id") != id);
+            let removed_invalid = invalid.len() < before;
+            let index = match agent_index(&config, &id) {
+                Ok(index) => index,
+                Err(_) if removed_invalid => {
+                    agent::save_agent_config(&path, &config)?;
+                    print(&args, &json!({ "removed": id }), || format!("Removed agent {}", id));
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            };
+            if str_field(&config.agents[index], "source") == "builtIn" {
+                return Err(CliError::Failed(format!(
+                    "{} is a built-in agent and would come back on the next config upgrade; use `aiden agent set {} enabled false` instead",
+                    id, id
+                )));
+            }
+            config.agents.remove(index);
+            agent::save_agent_config(&path, &config)?;
+            print(&args, &json!({ "removed": id }), || format!("Removed agent {}", id));
+            Ok(())
+        }
+        "set" => {
+            args.reject_unknown(&[])?;
+            let id = args.word(2, "agent id")?.to_string();
+            let field = args.word(3, "field name")?.to_string();
+            let raw = args.word(4, "value")?;
+            // true / false / 数字按 JSON 解析，其余按字符串保存
+            let value = match serde_json::from_str::<Value>(raw) {
+                Ok(value) if !value.is_object() && !value.is_array() => value,
+                _ => json!(raw),
+            };
+            let index = agent_index(&config, &id)?;
+            let agent = config.agents[index]
+                .as_object_mut()
+                .ok_or_else(|| CliError::Failed(format!("Agent {} is not an object", id)))?;
+            agent.insert(field.clone(), value.clone());
+            agent::save_agent_config(&path, &config)?;
+            print(&args, &json!({ "agent_id": id, "field": field, "value": value }), || {
+                format!("Set {} of agent {}", field, id)
+            });
+            Ok(())
+        }
+        other => Err(CliError::Usage(format!("Unknown agent command: {}", other))),
+    }
+}
+
+// ========= config ============
+
+/// 与应用启动时相同的校验
+fn validate_file(kind: ConfigKind, path: &Path) -> Vec<Diagnostic> {
+    if !path.exists() {
+        return vec![Diagnostic::error(
+            "",
+            "file does not exist (it is created on first launch)",
+        )];
+    }
+    match fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|text| serde_json::from_str::<Value>(&text).map_err(|e| e.to_string()))
+    {
+        Ok(json) => config::validate(kind, &json),
+        Err(e) => vec![Diagnostic::error("", e)],
+    }
+}
+
+fn config_command(host: &Headless, args: Args) -> CliResult {
+    let action = args.word(1, "config command")?.to_string();
+    args.reject_unknown(&[])?;
+    match action.as_str() {
+        "path" => {
+            let mut paths = Map::new();
+            for kind in ConfigKind::ALL {
+                let path = user_path(host, kind)?;
+                paths.insert(kind.name().to_string(), json!(path));
+            }
+            let value = Value::Object(paths);
+            print(&args, &value, || {
+                ConfigKind::ALL
+                    .iter()
+                    .map(|kind| format!("{:<6} {}", kind.name(), str_field(&value, kind.name())))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            });
+            Ok(())
+        }
+        "validate" => {
+            let mut reports = Vec::new();
+            for kind in ConfigKind::ALL {
+                let path = user_path(host, kind)?;
+                let diagnostics = validate_file(kind, &path);
+                reports.push(json!({
+                    "kind": kind,
+                    "path": path,
+                    "valid": !schema::has_errors(&diagnostics),
+                    "diagnostics": diagnostics,
+                }));
+            }
+            let valid = reports.iter().all(|report| report["valid"] == true);
+            print(&args, &Value::Array(reports.clone()), || {
+                reports
+                    .iter()
+                    .map(|report| {
+                        let mut text = format!(
+                            "{}: {}",
+                            str_field(report, "path"),
+                            if report["valid"] == true { "ok" } else { "invalid" }
+                        );
+                        for diagnostic in report["diagnostics"].as_array().into_iter().flatten() {
+                            let path = str_field(diagnostic, "path");
+                            text.push_str(&format!(
+                                "\n  - {}: {}{}",
+                                str_field(diagnostic, "severity"),
+                                if path.is_empty() { String::new() } else { format!("{}: ", path) },
+                                str_field(diagnostic, "message")
+                            ));
+                        }
+                        text
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            });
+            if valid {
+                Ok(())
+            } else {
+                Err(CliError::Failed("Config validation failed".into()))
+            }
+        }
+        "schema" => {
+            let name = args.word(2, "config kind")?;
+            let kind = ConfigKind::ALL
+                .into_iter()
+                .find(|kind| kind.name() == name)
+                .ok_or_else(|| CliError::Usage(format!("Unknown config kind: {}", name)))?;
+            let schema = kind.schema();
+            // schema 本身就是 JSON，--json 与否输出相同
+            print(&args, &schema, || {
+                serde_json::to_string_pretty(&schema).unwrap_or_default()
+            });
+            Ok(())
+        }
+        "upgrade" => {
+            let dry_run = args.switch("dry-run");
+            let mut reports = Vec::new();
+            for kind in ConfigKind::ALL {
+                let (default_path, user_path) = config::paths(host, kind)?;
+                let upgrade = config::plan_upgrade_files(kind, &default_path, &user_path)?;
+                if let (Some(upgrade), false) = (&upgrade, dry_run) {
+                    config::apply_upgrade(kind, &default_path, &user_path, upgrade)?;
+                }
+                reports.push((kind, user_path, upgrade));
+            }
+            let value = Value::Array(
+                reports
+                    .iter()
+                    .map(|(kind, path, upgrade)| {
+                        json!({
+                            "kind": kind,
+                            "path": path,
+                            "upgrade": upgrade,
+                            "applied": upgrade.is_some() && !dry_run,
+                        })
+                    })
+                    .collect(),
+            );
+            print(&args, &value, || {
+                reports
+                    .iter()
+                    .map(|(kind, path, upgrade)| match upgrade {
+                        Some(upgrade) if dry_run => {
+                            format!("{} ({}): would {}", kind.label(), path.display(), upgrade)
+                        }
+                        Some(upgrade) => {
+                            format!("{} ({}): {}", kind.label(), path.display(), upgrade)
+                        }
+                        None => format!("{} ({}): up to date", kind.label(), path.display()),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            });
+            Ok(())
+        }
+        other => Err(CliError::Usage(format!("Unknown config command: {}", other))),
+    }
+}
+
+/// 命令行子命令入口；不是子命令（GUI 启动）时返回 None，否则返回进程退出码
+///
+/// 只读写配置文件，不获取单实例锁，不启动 host_server。
+pub fn run(config: &Config, package_info: &PackageInfo) -> Option<i32> {
+    let raw: Vec<String> = std::env::args().skip(1).collect();
+    match raw.first().map(String::as_str) {
+        Some("mcp") | Some("agent") | Some("config") => {}
+        _ => return None,
+    }
+    attach_parent_console();
+    let host = Headless::new(config.clone(), package_info.clone());
+    let result = Args::parse(raw).and_then(|args| match args.words[0].as_str() {
+        "mcp" => mcp_command(&host, args),
+        "agent" => agent_command(&host, args),
+        _ => config_command(&host, args),
+    });
+    Some(exit_code(result))
+}
+
+/// 打印错误并换算成退出码：成功 0，执行失败 1，参数错误 2
+fn exit_code(result: CliResult) -> i32 {
+    match result {
+        Ok(()) => 0,
+        Err(CliError::Failed(message)) => {
+            eprintln!("Error: {}", message);
+            1
+        }
+        Err(CliError::Usage(message)) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            2
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args, CliError> {
+        Args::parse(args.iter().map(|arg| arg.to_string()).collect())
+    }
+
+    fn usage_message(result: Result<impl Sized, CliError>) -> String {
+        match result {
+            Err(CliError::Usage(message)) => message,
+            Err(other) => panic!("expected a usage error, got {:?}", other),
+            Ok(_) => panic!("expected a usage error"),
+        }
+    }
+
+    #[test]
+    fn parses_words_options_switches_and_trailing() {
+        let args = parse(&[
+            "mcp", "add", "fs", "--env", "A=1", "--env=B=2", "--json", "--", "npx", "--yes",
+        ])
+        .unwrap();
+        assert_eq!(args.words, ["mcp", "add", "fs"]);
+        assert_eq!(args.options["env"], ["A=1", "B=2"]);
+        // 重复的选项取最后一个
+        assert_eq!(args.option("env"), Some("B=2"));
+        assert!(args.switch("json"));
+        assert!(!args.switch("dry-run"));
+        assert_eq!(args.trailing, ["npx", "--yes"]);
+    }
+
+    #[test]
+    fn option_without_value_is_a_usage_error() {
+        assert_eq!(usage_message(parse(&["agent", "add", "a", "--name"])), "--name needs a value");
+        // 开关不需要值
+        assert!(parse(&["config", "upgrade", "--dry-run"]).is_ok());
+    }
+
+    #[test]
+    fn unknown_option_is_a_usage_error() {
+        let args = parse(&["mcp", "list", "--verbose", "1", "--json"]).unwrap();
+        assert_eq!(usage_message(args.reject_unknown(&[])), "Unknown option --verbose");
+        let args = parse(&["mcp", "add", "fs", "--url", "https://example.com/sse"]).unwrap();
+        assert!(args.reject_unknown(&["url", "env"]).is_ok());
+    }
+
+    #[test]
+    fn missing_word_is_a_usage_error() {
+        let args = parse(&["mcp"]).unwrap();
+        assert_eq!(usage_message(args.word(1, "mcp command")), "Missing mcp command");
+        assert_eq!(args.word(0, "command").ok(), Some("mcp"));
+    }
+
+    #[test]
+    fn exit_codes() {
+        assert_eq!(exit_code(Ok(())), 0);
+        assert_eq!(exit_code(Err(CliError::Failed("No MCP server named x".into()))), 1);
+        assert_eq!(exit_code(Err(CliError::Usage("Missing server name".into()))), 2);
+        let args = parse(&["agent", "remove"]).unwrap();
+        assert_eq!(exit_code(args.word(2, "agent id").map(|_| ())), 2);
+    }
+}
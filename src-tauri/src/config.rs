@@ -0,0 +1,505 @@
+use crate::agent::AgentConfig;
+use crate::config_history::{self, SnapshotReason};
+use crate::constants::{CONFIG_BACKUP_EXTENSION, CONFIG_QUARANTINE_TAG};
+use crate::host::Host;
+use crate::mcp::MCPConfig;
+use crate::schema::{self, Diagnostic, JsonSchema, Severity};
+use crate::{agent, mcp};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use tauri::Config;
+
+/// Config 目录下由应用管理的配置文件
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigKind {
+    Mcp,
+    Agent,
+}
+
+impl ConfigKind {
+    pub const ALL: [ConfigKind; 2] = [ConfigKind::Mcp, ConfigKind::Agent];
+
+    /// 命令行与 JSON 输出中使用的名称
+    pub fn name(self) -> &'static str {
+        match self {
+            ConfigKind::Mcp => "mcp",
+            ConfigKind::Agent => "agent",
+        }
+    }
+
+    /// 日志中使用的名称
+    pub fn label(self) -> &'static str {
+        match self {
+            ConfigKind::Mcp => "MCP",
+            ConfigKind::Agent => "Agent",
+        }
+    }
+
+    pub fn file_name(self) -> &'static str {
+        match self {
+            ConfigKind::Mcp => "mcp.config.json",
+            ConfigKind::Agent => "agent.config.json",
+        }
+    }
+
+    pub fn user_path(self, config: &Config) -> Option<PathBuf> {
+        match self {
+            ConfigKind::Mcp => mcp::get_user_config_path(config),
+            ConfigKind::Agent => agent::get_user_config_path(config),
+        }
+    }
+
+    /// 随应用打包的默认配置
+    pub fn default_path<H: Host>(self, app: &H) -> Option<PathBuf> {
+        app.resource_dir()
+            .map(|dir| dir.join("resources").join(self.file_name()))
+    }
+
+    /// 诊断路径是否落在单个条目（某个 server / agent）内；这类错误只影响该条目
+    pub fn is_entry_path(self, path: &str) -> bool {
+        let field = match self {
+            ConfigKind::Mcp => "mcpServers",
+            ConfigKind::Agent => "agents",
+        };
+        path.strip_prefix(field)
+            .map_or(false, |rest| rest.starts_with('.') || rest.starts_with('['))
+    }
+
+    /// 由 Rust 类型生成的 JSON Schema
+    pub fn schema(self) -> Value {
+        match self {
+            ConfigKind::Mcp => schema::document(self.file_name(), MCPConfig::json_schema()),
+            ConfigKind::Agent => schema::document(self.file_name(), AgentConfig::json_schema()),
+        }
+    }
+
+    /// 把默认配置中由应用维护的条目合并进用户配置
+    fn merge_defaults(self, user_json: &mut Value, default_json: &Value) {
+        match self {
+            ConfigKind::Mcp => mcp::merge_default_servers(user_json, default_json),
+            ConfigKind::Agent => agent::merge_builtin_agents(user_json, default_json),
+        }
+    }
+}
+
+/// 用户配置相对于打包的默认配置需要做的升级
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ConfigUpgrade {
+    /// 首次安装，用户配置不存在，复制默认配置
+    Create,
+    /// syncVersion 落后，用默认配置整体覆盖
+    ForceSync { from: String, to: String },
+    /// version 落后，保留用户条目并更新默认条目
+    Merge {
+        from: String,
+        to: String,
+        #[serde(skip)]
+        config: Value,
+    },
+}
+
+impl std::fmt::Display for ConfigUpgrade {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigUpgrade::Create => write!(f, "create from the bundled default"),
+            ConfigUpgrade::ForceSync { from, to } => {
+                write!(f, "replace with the bundled default (syncVersion {} -> {})", from, to)
+            }
+            ConfigUpgrade::Merge { from, to, .. } => {
+                write!(f, "merge bundled entries (version {} -> {})", from, to)
+            }
+        }
+    }
+}
+
+fn version_field(json: &Value, field: &str) -> Version {
+    json.get(field)
+        .and_then(|v| v.as_str())
+        .and_then(|v| Version::parse(v).ok())
+        .unwrap_or_else(|| Version::new(0, 0, 0))
+}
+
+/// 比较默认配置与用户配置，返回需要做的升级；已是最新时返回 None
+pub fn plan_upgrade(
+    kind: ConfigKind,
+    default_json: &Value,
+    user_json: Option<&Value>,
+) -> Option<ConfigUpgrade> {
+    let user_json = match user_json {
+        Some(user_json) => user_json,
+        None => return Some(ConfigUpgrade::Create),
+    };
+
+    // ========= Step 1: 强制 syncVersion 同步判断 ============
+    let default_sync = version_field(default_json, "syncVersion");
+    let user_sync = version_field(user_json, "syncVersion");
+    log::info!(
+        "{} config syncVersion: default={}, user={}",
+        kind.label(),
+        default_sync,
+        user_sync
+    );
+    if default_sync > user_sync {
+        return Some(ConfigUpgrade::ForceSync {
+            from: user_sync.to_string(),
+            to: default_sync.to_string(),
+        });
+    }
+
+    // ========= Step 2: 正常 version 增量更新逻辑 ============
+    let default_version = version_field(default_json, "version");
+    let user_version = version_field(user_json, "version");
+    log::info!(
+        "{} config version: default={}, user={}",
+        kind.label(),
+        default_version,
+        user_version
+    );
+    if default_version <= user_version {
+        return None;
+    }
+    let mut config = user_json.clone();
+    kind.merge_defaults(&mut config, default_json);
+    config["version"] = Value::String(default_version.to_string());
+    Some(ConfigUpgrade::Merge {
+        from: user_version.to_string(),
+        to: default_version.to_string(),
+        config,
+    })
+}
+
+/// 校验配置内容；version 与 syncVersion 无法按 semver 解析时升级会当作 0.0.0，只提示
+pub fn validate(kind: ConfigKind, json: &Value) -> Vec<Diagnostic> {
+    let mut diagnostics = match kind {
+        ConfigKind::Mcp => mcp::validate_mcp_config(json),
+        ConfigKind::Agent => agent::validate_agent_config(json),
+    };
+    for field in ["version", "syncVersion"] {
+        if let Some(version) = json.get(field).and_then(Value::as_str) {
+            if Version::parse(version).is_err() {
+                diagnostics.push(Diagnostic::warning(
+                    field,
+                    format!("{:?} is not a semver version and is treated as 0.0.0 on upgrade", version),
+                ));
+            }
+        }
+    }
+    diagnostics
+}
+
+/// 写入前校验；有错误时拒绝写入，警告不影响
+pub fn check(kind: ConfigKind, json: &Value) -> Result<(), String> {
+    let diagnostics = validate(kind, json);
+    if schema::has_errors(&diagnostics) {
+        return Err(format!(
+            "Invalid {} config: {}",
+            kind.label(),
+            schema::describe_errors(&diagnostics)
+        ));
+    }
+    Ok(())
+}
+
+/// 校验前端编辑中的配置
+#[tauri::command]
+pub fn validate_config(kind: ConfigKind, json: Value) -> Vec<Diagnostic> {
+    validate(kind, &json)
+}
+
+#[tauri::command]
+pub fn get_config_schema(kind: ConfigKind) -> Value {
+    kind.schema()
+}
+
+fn read_json(path: &Path, what: &str) -> Result<Value, String> {
+    let text =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", what, e))?;
+    serde_json::from_str(&text).map_err(|e| format!("Invalid JSON in {}: {}", what, e))
+}
+
+/// eg: Config/mcp.config.json -> Config/mcp.config.json.bak
+pub fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(CONFIG_BACKUP_EXTENSION);
+    path.with_file_name(name)
+}
+
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> io::Result<()> {
+    File::open(dir)?.sync_all()
+}
+
+/// Windows 无法打开目录做 fsync，rename 本身已是原子替换
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+/// 原子替换：先写同目录下的临时文件并 fsync，再 rename 覆盖并 fsync 目录；中途崩溃或磁盘写满时原文件保持完整
+fn replace_file(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let mut tmp_name = std::ffi::OsString::from(".");
+    tmp_name.push(path.file_name().unwrap_or_default());
+    tmp_name.push(format!(".tmp-{}", std::process::id()));
+    let tmp = dir.join(tmp_name);
+    let result = File::create(&tmp)
+        .and_then(|mut file| {
+            file.write_all(contents)?;
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&tmp, path))
+        .and_then(|_| sync_dir(dir));
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result
+}
+
+/// 写入配置文件；当前文件是合法 JSON 时先把它保存为 .bak
+pub fn write_config_file(path: &Path, contents: &str) -> Result<(), String> {
+    if let Ok(current) = fs::read(path) {
+        if serde_json::from_slice::<Value>(&current).is_ok() {
+            if let Err(e) = replace_file(&backup_path(path), &current) {
+                log::warn!("Failed to back up {}: {}", path.display(), e);
+            }
+        }
+    }
+    replace_file(path, contents.as_bytes())
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+pub fn write_config_json<T: Serialize>(path: &Path, config: &T) -> Result<(), String> {
+    let json_str = serde_json::to_string_pretty(config).map_err(|e| e.to_string())?;
+    write_config_file(path, &json_str)
+}
+
+/// 读取用户配置；主文件读不出合法 JSON 时退回 .bak
+pub fn read_config_file(path: &Path) -> Result<Value, String> {
+    read_json(path, "user config").or_else(|e| {
+        let backup = backup_path(path);
+        let json = read_json(&backup, "backup config").map_err(|_| e.clone())?;
+        log::warn!("{}, using backup {}", e, backup.display());
+        Ok(json)
+    })
+}
+
+/// 读取两份配置文件并计算升级；用户配置不存在时不读取默认配置
+pub fn plan_upgrade_files(
+    kind: ConfigKind,
+    default_path: &Path,
+    user_path: &Path,
+) -> Result<Option<ConfigUpgrade>, String> {
+    if !user_path.exists() {
+        return Ok(Some(ConfigUpgrade::Create));
+    }
+    let default_json = read_json(default_path, "default config")?;
+    let user_json = read_config_file(user_path)?;
+    Ok(plan_upgrade(kind, &default_json, Some(&user_json)))
+}
+
+pub fn apply_upgrade(
+    kind: ConfigKind,
+    default_path: &Path,
+    user_path: &Path,
+    upgrade: &ConfigUpgrade,
+) -> Result<(), String> {
+    match upgrade {
+        ConfigUpgrade::Create => {
+            fs::read_to_string(default_path)
+                .map_err(|e| e.to_string())
+                .and_then(|text| write_config_file(user_path, &text))
+                .map_err(|e| format!("Copy {} config failed: {}", kind.label(), e))?;
+            log::info!("{} config initialized: {:?}", kind.label(), user_path);
+        }
+        ConfigUpgrade::ForceSync { from, to } => {
+            config_history::snapshot(kind, user_path, SnapshotReason::ForceSync);
+            fs::read_to_string(default_path)
+                .map_err(|e| e.to_string())
+                .and_then(|text| write_config_file(user_path, &text))
+                .map_err(|e| format!("Forced sync copy failed: {}", e))?;
+            log::info!(
+                "{} config forcibly synced due to syncVersion mismatch: {} -> {}",
+                kind.label(),
+                from,
+                to
+            );
+        }
+        ConfigUpgrade::Merge { from, to, config } => {
+            log::info!("{} config update needed: {} -> {}", kind.label(), from, to);
+            config_history::snapshot(kind, user_path, SnapshotReason::Upgrade);
+            write_config_json(user_path, config).map_err(|e| {
+                format!("Failed to write updated {} config: {}", kind.label(), e)
+            })?;
+            log::info!("{} config upgraded successfully.", kind.label());
+        }
+    }
+    Ok(())
+}
+
+/// 默认配置与用户配置的路径
+pub fn paths<H: Host>(app: &H, kind: ConfigKind) -> Result<(PathBuf, PathBuf), String> {
+    let user_path = kind
+        .user_path(&app.app_config())
+        .ok_or_else(|| format!("Failed to get {} config file path.", kind.label()))?;
+    let default_path = kind
+        .default_path(app)
+        .ok_or_else(|| format!("Cannot find default {} config in resources.", kind.label()))?;
+    Ok((default_path, user_path))
+}
+
+/// 用户配置无法解析时返回问题描述；单个条目的错误只记日志，读取时跳过该条目，不影响其他条目
+fn check_user_file(kind: ConfigKind, user_path: &Path) -> Option<String> {
+    let json = match read_json(user_path, "user config") {
+        Ok(json) => json,
+        Err(e) => return Some(e),
+    };
+    let (entry_errors, file_errors): (Vec<Diagnostic>, Vec<Diagnostic>) = validate(kind, &json)
+        .into_iter()
+        .filter(|d| d.severity == Severity::Error)
+        .partition(|d| kind.is_entry_path(&d.path));
+    if !entry_errors.is_empty() {
+        log::warn!(
+            "{} config has invalid entries, they are skipped until fixed: {}",
+            kind.label(),
+            schema::describe_errors(&entry_errors)
+        );
+    }
+    if file_errors.is_empty() {
+        None
+    } else {
+        Some(schema::describe_errors(&file_errors))
+    }
+}
+
+/// 把无法使用的用户配置移到同目录下，返回新路径；主文件随之不存在，之后的写入不会把它备份成 .bak
+fn quarantine(kind: ConfigKind, user_path: &Path) -> Result<PathBuf, String> {
+    let stem = kind.file_name().trim_end_matches(".json");
+    let time = chrono::Local::now().format("%Y%m%d-%H%M%S-%3f");
+    let mut target =
+        user_path.with_file_name(format!("{}.{}-{}.json", stem, CONFIG_QUARANTINE_TAG, time));
+    // 同一毫秒内连续隔离（如恢复 .bak 后立即检查）时加序号，避免覆盖上一份
+    let mut n = 1;
+    while target.exists() {
+        target = user_path.with_file_name(format!(
+            "{}.{}-{}-{}.json",
+            stem, CONFIG_QUARANTINE_TAG, time, n
+        ));
+        n += 1;
+    }
+    fs::rename(user_path, &target)
+        .map_err(|e| format!("Failed to quarantine {} config: {}", kind.label(), e))?;
+    Ok(target)
+}
+
+/// 主文件不是合法 JSON（如写入中途崩溃）而 .bak 可用时，另存主文件后用 .bak 恢复
+fn restore_backup(kind: ConfigKind, user_path: &Path) -> Result<(), String> {
+    let backup = backup_path(user_path);
+    if !user_path.exists() || read_json(user_path, "user config").is_ok() {
+        return Ok(());
+    }
+    let text = match fs::read_to_string(&backup) {
+        Ok(text) if serde_json::from_str::<Value>(&text).is_ok() => text,
+        _ => return Ok(()),
+    };
+    let target = quarantine(kind, user_path)?;
+    replace_file(user_path, text.as_bytes())
+        .map_err(|e| format!("Failed to restore {} config backup: {}", kind.label(), e))?;
+    log::warn!(
+        "{} config is not valid JSON, moved to {} and restored from {}",
+        kind.label(),
+        target.display(),
+        backup.display()
+    );
+    Ok(())
+}
+
+/// 启动时创建或升级用户配置；用户配置损坏时另存一份后恢复备份或默认配置，不让应用启动失败
+pub fn init_config<H: Host>(app: &H, kind: ConfigKind) -> Result<(), String> {
+    let (default_path, user_path) = paths(app, kind)?;
+    restore_backup(kind, &user_path)?;
+    let upgrade = match user_path.exists().then(|| check_user_file(kind, &user_path)).flatten() {
+        Some(problem) => {
+            let target = quarantine(kind, &user_path)?;
+            log::error!(
+                "{} config is unusable ({}), moved to {} and restoring defaults",
+                kind.label(),
+                problem,
+                target.display()
+            );
+            Some(ConfigUpgrade::Create)
+        }
+        None => plan_upgrade_files(kind, &default_path, &user_path)?,
+    };
+    match upgrade {
+        Some(upgrade) => apply_upgrade(kind, &default_path, &user_path, &upgrade),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn agent_config(version: &str, sync_version: &str) -> Value {
+        json!({
+            "version": version,
+            "syncVersion": sync_version,
+            "agents": [
+                { "agent_id": "builtin", "agent_name": "Builtin", "source": "builtIn" },
+                { "agent_id": "mine", "agent_name": "Mine", "source": "custom" },
+            ],
+        })
+    }
+
+    #[test]
+    fn missing_user_config_is_created() {
+        let default = agent_config("1.1.0", "1.0.0");
+        assert!(matches!(
+            plan_upgrade(ConfigKind::Agent, &default, None),
+            Some(ConfigUpgrade::Create)
+        ));
+    }
+
+    #[test]
+    fn newer_sync_version_forces_sync() {
+        let default = agent_config("1.1.0", "2.0.0");
+        let user = agent_config("1.0.0", "1.0.0");
+        match plan_upgrade(ConfigKind::Agent, &default, Some(&user)) {
+            Some(ConfigUpgrade::ForceSync { from, to }) => {
+                assert_eq!((from.as_str(), to.as_str()), ("1.0.0", "2.0.0"))
+            }
+            other => panic!("expected ForceSync, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn newer_version_merges_and_keeps_user_entries() {
+        let default = agent_config("1.1.0", "1.0.0");
+        let mut user = agent_config("1.0.0", "1.0.0");
+        user["agents"][1]["agent_name"] = json!("Renamed");
+        match plan_upgrade(ConfigKind::Agent, &default, Some(&user)) {
+            Some(ConfigUpgrade::Merge { from, to, config }) => {
+                assert_eq!((from.as_str(), to.as_str()), ("1.0.0", "1.1.0"));
+                assert_eq!(config["version"], "1.1.0");
+                let agents = config["agents"].as_array().unwrap();
+                assert_eq!(agents.len(), 2);
+                assert!(agents.iter().any(|a| a["agent_name"] == "Renamed"));
+            }
+            other => panic!("expected Merge, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn same_version_needs_no_upgrade() {
+        let default = agent_config("1.0.0", "1.0.0");
+        assert!(plan_upgrade(ConfigKind::Agent, &default, Some(&default)).is_none());
+    }
+}
@@ -0,0 +1,327 @@
+use crate::config::{self, ConfigKind};
+use crate::constants::{CONFIG_HISTORY_DIR, CONFIG_HISTORY_MAX_AGE, CONFIG_HISTORY_MAX_SNAPSHOTS};
+use crate::schema;
+use serde::Serialize;
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::AppHandle;
+
+/// 快照产生的原因：快照保存的是被这次操作替换掉的内容
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SnapshotReason {
+    /// write_*_config 或命令行修改
+    Write,
+    /// 启动时按 version 合并默认配置
+    Upgrade,
+    /// 启动时按 syncVersion 整体覆盖
+    ForceSync,
+    /// 恢复快照
+    Restore,
+}
+
+impl SnapshotReason {
+    const ALL: [SnapshotReason; 4] = [
+        SnapshotReason::Write,
+        SnapshotReason::Upgrade,
+        SnapshotReason::ForceSync,
+        SnapshotReason::Restore,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            SnapshotReason::Write => "write",
+            SnapshotReason::Upgrade => "upgrade",
+            SnapshotReason::ForceSync => "force_sync",
+            SnapshotReason::Restore => "restore",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigSnapshot {
+    /// 文件名（不含 .json），用于 diff / restore
+    pub id: String,
+    pub kind: ConfigKind,
+    pub reason: SnapshotReason,
+    /// 毫秒时间戳
+    pub timestamp: u64,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// 快照到当前配置的一处差异，path 形如 mcpServers.search.url、agents[2]
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigChange {
+    pub path: String,
+    pub change: ChangeKind,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+/// eg: Config/history/
+fn history_dir(user_path: &Path) -> PathBuf {
+    user_path
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join(CONFIG_HISTORY_DIR)
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// 文件名格式：<kind>-<毫秒时间戳>-<reason>.json
+fn parse_id(kind: ConfigKind, id: &str) -> Option<(u64, SnapshotReason)> {
+    let rest = id.strip_prefix(kind.name())?.strip_prefix('-')?;
+    let (timestamp, reason) = rest.split_once('-')?;
+    let reason = SnapshotReason::ALL
+        .into_iter()
+        .find(|r| r.name() == reason)?;
+    Some((timestamp.parse().ok()?, reason))
+}
+
+fn list(kind: ConfigKind, user_path: &Path) -> Vec<(ConfigSnapshot, PathBuf)> {
+    let entries = match fs::read_dir(history_dir(user_path)) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+    let mut snapshots: Vec<(ConfigSnapshot, PathBuf)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            let id = path.file_name()?.to_str()?.strip_suffix(".json")?.to_string();
+            let (timestamp, reason) = parse_id(kind, &id)?;
+            let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+            Some((
+                ConfigSnapshot {
+                    id,
+                    kind,
+                    reason,
+                    timestamp,
+                    size,
+                },
+                path,
+            ))
+        })
+        .collect();
+    snapshots.sort_by(|a, b| b.0.timestamp.cmp(&a.0.timestamp));
+    snapshots
+}
+
+/// 超出份数或天数的快照删除，最新一份始终保留
+fn prune(kind: ConfigKind, user_path: &Path) {
+    let cutoff = now_millis().saturating_sub(CONFIG_HISTORY_MAX_AGE.as_millis() as u64);
+    for (i, (snapshot, path)) in list(kind, user_path).into_iter().enumerate() {
+        if i >= CONFIG_HISTORY_MAX_SNAPSHOTS || (i > 0 && snapshot.timestamp < cutoff) {
+            if let Err(e) = fs::remove_file(&path) {
+                log::warn!("Failed to remove config snapshot {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+/// 在替换用户配置之前保存当前内容；与最新快照相同时不重复保存。失败只记日志，不影响写入
+pub fn snapshot(kind: ConfigKind, user_path: &Path, reason: SnapshotReason) {
+    let current = match fs::read(user_path) {
+        Ok(current) => current,
+        Err(_) => return,
+    };
+    let latest = list(kind, user_path).into_iter().next();
+    if let Some((_, latest)) = latest {
+        if fs::read(latest).map_or(false, |bytes| bytes == current) {
+            return;
+        }
+    }
+    let dir = history_dir(user_path);
+    let path = dir.join(format!("{}-{}-{}.json", kind.name(), now_millis(), reason.name()));
+    let result = fs::create_dir_all(&dir).and_then(|_| fs::write(&path, &current));
+    match result {
+        Ok(()) => prune(kind, user_path),
+        Err(e) => log::warn!("Failed to save {} config snapshot: {}", kind.label(), e),
+    }
+}
+
+fn find(kind: ConfigKind, user_path: &Path, id: &str) -> Result<PathBuf, String> {
+    list(kind, user_path)
+        .into_iter()
+        .find(|(snapshot, _)| snapshot.id == id)
+        .map(|(_, path)| path)
+        .ok_or_else(|| format!("No {} config snapshot {}", kind.label(), id))
+}
+
+fn read_snapshot(path: &Path) -> Result<Value, String> {
+    let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&text).map_err(|e| format!("Invalid JSON in snapshot: {}", e))
+}
+
+fn child_path(parent: &str, key: &str) -> String {
+    if parent.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", parent, key)
+    }
+}
+
+fn diff_values(path: &str, before: &Value, after: &Value, changes: &mut Vec<ConfigChange>) {
+    match (before, after) {
+        (Value::Object(before), Value::Object(after)) => {
+            for (key, old) in before {
+                match after.get(key) {
+                    Some(new) => diff_values(&child_path(path, key), old, new, changes),
+                    None => changes.push(ConfigChange {
+                        path: child_path(path, key),
+                        change: ChangeKind::Removed,
+                        before: Some(old.clone()),
+                        after: None,
+                    }),
+                }
+            }
+            for (key, new) in after {
+                if !before.contains_key(key) {
+                    changes.push(ConfigChange {
+                        path: child_path(path, key),
+                        change: ChangeKind::Added,
+                        before: None,
+                        after: Some(new.clone()),
+                    });
+                }
+            }
+        }
+        (Value::Array(before), Value::Array(after)) => {
+            for i in 0..before.len().max(after.len()) {
+                let item_path = format!("{}[{}]", path, i);
+                match (before.get(i), after.get(i)) {
+                    (Some(old), Some(new)) => diff_values(&item_path, old, new, changes),
+                    (old, new) => changes.push(ConfigChange {
+                        path: item_path,
+                        change: if old.is_some() {
+                            ChangeKind::Removed
+                        } else {
+                            ChangeKind::Added
+                        },
+                        before: old.cloned(),
+                        after: new.cloned(),
+                    }),
+                }
+            }
+        }
+        _ if before != after => changes.push(ConfigChange {
+            path: path.to_string(),
+            change: ChangeKind::Changed,
+            before: Some(before.clone()),
+            after: Some(after.clone()),
+        }),
+        _ => {}
+    }
+}
+
+fn user_path(app: &AppHandle, kind: ConfigKind) -> Result<PathBuf, String> {
+    kind.user_path(&app.config())
+        .ok_or_else(|| "配置路径不存在".to_string())
+}
+
+/// 列出快照，最新的在前
+#[tauri::command]
+pub fn list_config_snapshots(app: AppHandle, kind: ConfigKind) -> Result<Vec<ConfigSnapshot>, String> {
+    let path = user_path(&app, kind)?;
+    Ok(list(kind, &path).into_iter().map(|(snapshot, _)| snapshot).collect())
+}
+
+/// 从快照到当前配置的变化
+#[tauri::command]
+pub fn diff_config_snapshot(
+    app: AppHandle,
+    kind: ConfigKind,
+    id: String,
+) -> Result<Vec<ConfigChange>, String> {
+    let path = user_path(&app, kind)?;
+    let before = read_snapshot(&find(kind, &path, &id)?)?;
+    let after = config::read_config_file(&path).unwrap_or(Value::Null);
+    let mut changes = Vec::new();
+    diff_values("", &before, &after, &mut changes);
+    Ok(changes)
+}
+
+/// 用快照替换当前配置；当前配置先保存为快照，恢复本身也可以撤销
+#[tauri::command]
+pub fn restore_config_snapshot(app: AppHandle, kind: ConfigKind, id: String) -> Result<(), String> {
+    let path = user_path(&app, kind)?;
+    let snapshot_path = find(kind, &path, &id)?;
+    let json = read_snapshot(&snapshot_path)?;
+    let diagnostics = config::validate(kind, &json);
+    if schema::has_errors(&diagnostics) {
+        return Err(format!(
+            "Snapshot {} is not a valid {} config: {}",
+            id,
+            kind.label(),
+            schema::describe_errors(&diagnostics)
+        ));
+    }
+    let text = fs::read_to_string(&snapshot_path).map_err(|e| e.to_string())?;
+    snapshot(kind, &path, SnapshotReason::Restore);
+    config::write_config_file(&path, &text)?;
+    log::info!("{} config restored from snapshot {}", kind.label(), id);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn diff_reports_nested_paths() {
+        let before = json!({
+            "mcpServers": {
+                "search": { "url": "https://a.example/sse", "disabled": false },
+                "old": { "command": "npx" },
+            },
+            "agents": [{ "agent_id": "a" }, { "agent_id": "b" }],
+        });
+        let after = json!({
+            "mcpServers": {
+                "search": { "url": "https://b.example/sse", "disabled": false },
+                "new": { "command": "uvx" },
+            },
+            "agents": [{ "agent_id": "a", "enabled": true }],
+        });
+        let mut changes = Vec::new();
+        diff_values("", &before, &after, &mut changes);
+        let mut summary: Vec<(String, ChangeKind)> = changes
+            .into_iter()
+            .map(|change| (change.path, change.change))
+            .collect();
+        summary.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            summary,
+            [
+                ("agents[0].enabled".to_string(), ChangeKind::Added),
+                ("agents[1]".to_string(), ChangeKind::Removed),
+                ("mcpServers.new".to_string(), ChangeKind::Added),
+                ("mcpServers.old".to_string(), ChangeKind::Removed),
+                ("mcpServers.search.url".to_string(), ChangeKind::Changed),
+            ]
+        );
+    }
+
+    #[test]
+    fn equal_values_have_no_diff() {
+        let value = json!({ "agents": [{ "agent_id": "a" }] });
+        let mut changes = Vec::new();
+        diff_values("", &value, &value, &mut changes);
+        assert!(changes.is_empty());
+    }
+}
@@ -1,3 +1,87 @@
 pub const HOST_SERVER_READY_TEXT: &str = "running on http://127.0.0.1:";
 pub const HOST_SERVER_EVENT_NAME: &str = "host_server_ready";
-pub const PORTS_TO_KILL: &[u16] = &[5001];
+// 启动时检查的固定端口；上次遗留的进程已由 sweep_orphans 结束，剩下的占用者报告给前端
+pub const PORTS_TO_CHECK: &[u16] = &[5001];
+pub const PORT_CONFLICT_EVENT: &str = "port_conflict";
+pub const SECOND_INSTANCE_EVENT: &str = "second_instance";
+// 第二次启动时，参数中以该 scheme 开头的才当作 deep link 转发
+pub const DEEP_LINK_SCHEME: &str = "aiden";
+pub const HOST_SERVER_EXITED_EVENT: &str = "host_server_exited";
+pub const HOST_SERVER_RESTARTING_EVENT: &str = "host_server_restarting";
+pub const HOST_SERVER_FAILED_EVENT: &str = "host_server_failed";
+// 崩溃重启：指数退避，连续崩溃超过上限后放弃；稳定运行一段时间后计数清零
+pub const HOST_SERVER_RESTART_BACKOFF_MS: u64 = 1_000;
+pub const HOST_SERVER_RESTART_BACKOFF_MAX_MS: u64 = 30_000;
+pub const HOST_SERVER_MAX_RESTARTS: u32 = 5;
+pub const HOST_SERVER_STABLE_UPTIME_SECS: u64 = 60;
+// 停止时先 SIGTERM，grace 时间后 SIGKILL；STOP_TIMEOUT 需大于 grace
+pub const HOST_SERVER_SHUTDOWN_GRACE: std::time::Duration = std::time::Duration::from_secs(5);
+pub const HOST_SERVER_STOP_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+// 定期刷新 host_server 的后代进程并写入 run_state，供下次启动时清理崩溃遗留
+pub const HOST_SERVER_TREE_REFRESH: std::time::Duration = std::time::Duration::from_secs(15);
+// ready 探测：stdout 文本只作为快速路径，以健康检查为准
+pub const HOST_SERVER_HEALTH_PATH: &str = "/health";
+pub const HOST_SERVER_READY_TIMEOUT_ENV: &str = "AIDEN_HOST_SERVER_READY_TIMEOUT";
+pub const HOST_SERVER_READY_TIMEOUT_SECS: u64 = 60;
+pub const HOST_SERVER_STDERR_TAIL_LINES: usize = 20;
+// 登录 shell 解析 PATH：交互式配置可能卡住，超时后放弃并使用当前进程的 PATH
+pub const SHELL_PATH_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+pub const SHELL_ENV_MARKER: &str = "__AIDEN_ENV__";
+// MCP 运行时探测：对每个 stdio server 的 command 执行 --version
+pub const MCP_RUNTIME_PROBE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+pub const MCP_RUNTIME_MISSING_EVENT: &str = "mcp_runtime_missing";
+// Windows 下启动子进程不弹出控制台窗口
+#[cfg(target_os = "windows")]
+pub const CREATE_NO_WINDOW: u32 = 0x08000000;
+// 动态端口在交给 host_server 之前可能被其他进程占用，检测到 "address in use" 后换端口重试
+pub const HOST_SERVER_PORT_RETRIES: u32 = 3;
+pub const HOST_SERVER_PORT_IN_USE_PATTERNS: &[&str] = &[
+    "address already in use",
+    "address in use",
+    "errno 98",
+    "errno 48",
+    "errno 10048",
+];
+// 开发模式下 host_server 的固定端口，可通过环境变量覆盖
+pub const HOST_SERVER_DEV_PORT: u16 = 6888;
+pub const HOST_SERVER_DEV_PORT_ENV: &str = "AIDEN_HOST_SERVER_PORT";
+// 外部 host_server：设置后不再启动内置二进制，只对该地址做健康检查（后端调试用）
+pub const HOST_SERVER_EXTERNAL_URL_ENV: &str = "AIDEN_HOST_SERVER_URL";
+pub const HOST_SERVER_EXTERNAL_URL_FLAG: &str = "--host-server-url";
+// 每次启动 host_server 生成的授权密钥，经 stdin 传给子进程；headless 模式按该变量名输出
+pub const HOST_SERVER_SECRET_ENV: &str = "AIDEN_HOST_SERVER_SECRET";
+pub const HOST_SERVER_SECRET_BYTES: usize = 32;
+// 从该版本起 host_server 在 --enable_authorization 时从 stdin 读取密钥；更早的版本仍走 /authorization/token
+pub const HOST_SERVER_STDIN_SECRET_VERSION: &str = "0.3.0";
+// host_server 完整性校验：与二进制同目录的清单文件，记录 sha256 与期望版本
+pub const HOST_SERVER_MANIFEST_NAME: &str = "host_server.manifest.json";
+pub const HOST_SERVER_VERSION_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+// host_server 独立更新：发布源地址（未配置时不启用）、新版本 ready 超时（超时回滚）、保留的版本数
+pub const HOST_SERVER_FEED_URL_ENV: &str = "AIDEN_HOST_SERVER_FEED_URL";
+pub const HOST_SERVER_UPDATED_EVENT: &str = "host_server_updated";
+pub const HOST_SERVER_UPDATE_READY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(90);
+pub const HOST_SERVER_UPDATE_RETAIN: usize = 2;
+// 更新包的 minisign 公钥（base64），与 tauri.conf.json 中应用更新的 pubkey 相同，用同一把私钥签名
+pub const HOST_SERVER_UPDATE_PUBKEY: &str = "dW50cnVzdGVkIGNvbW1lbnQ6IG1pbmlzaWduIHB1YmxpYyBrZXk6IEQ1MkQ3RERCOUU0RUYwOTMKUldTVDhFNmUyMzB0MVE4MjE0SStVWlVpNXN4dTR0REdDQlV2eHBDUzNJUzNsa1pPNTB3TVV5RU4K";
+// host_server 输出的内存环形缓冲，供设置页实时查看
+pub const HOST_SERVER_LOG_BUFFER_LINES: usize = 2_000;
+pub const HOST_SERVER_LOG_EVENT: &str = "host_server_log";
+// host_server 进程树资源监控：周期推送事件，以及单个 MCP server 的告警阈值（可通过环境变量覆盖）
+pub const PROCESS_STATS_EVENT: &str = "process_stats";
+pub const PROCESS_STATS_WARNING_EVENT: &str = "process_stats_warning";
+pub const PROCESS_STATS_RSS_LIMIT_MB: f64 = 1024.0;
+pub const PROCESS_STATS_RSS_LIMIT_ENV: &str = "AIDEN_MCP_RSS_LIMIT_MB";
+pub const PROCESS_STATS_CPU_LIMIT_PERCENT: f64 = 90.0;
+pub const PROCESS_STATS_CPU_LIMIT_ENV: &str = "AIDEN_MCP_CPU_LIMIT_PERCENT";
+// 后台检查阈值的采样间隔，前端不打开资源面板时也会告警（headless 下只写日志）
+pub const PROCESS_STATS_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+// 无窗口模式：不创建 webview，前台运行并把 host_server 地址和密钥打印到 stdout
+pub const HEADLESS_FLAG: &str = "--headless";
+// 启动时无法使用的用户配置另存为 <name>.corrupt-<时间>.json 后恢复默认配置
+pub const CONFIG_QUARANTINE_TAG: &str = "corrupt";
+// 配置文件最近一次可用版本的备份：<name>.bak，主文件无法解析时读取
+pub const CONFIG_BACKUP_EXTENSION: &str = "bak";
+// 配置快照：保存在 Config/history/，每种配置最多保留的份数与天数（最新一份不按天数清理）
+pub const CONFIG_HISTORY_DIR: &str = "history";
+pub const CONFIG_HISTORY_MAX_SNAPSHOTS: usize = 50;
+pub const CONFIG_HISTORY_MAX_AGE: std::time::Duration = std::time::Duration::from_secs(30 * 24 * 60 * 60);
@@ -0,0 +1,172 @@
+use crate::constants::{
+    HEADLESS_FLAG, HOST_SERVER_EVENT_NAME, HOST_SERVER_EXTERNAL_URL_ENV, HOST_SERVER_SECRET_ENV,
+    PORTS_TO_CHECK,
+};
+use crate::host::Host;
+use crate::host_log::HostServerLogs;
+use crate::host_server::{self, HostServerProcess, HostServerState, HostServerStatus};
+use crate::mcp_probe::McpRuntimesMissing;
+use crate::ports::PortConflicts;
+use crate::process_stats::{self, ProcessMonitor};
+use crate::single_instance::SingleInstance;
+use crate::{agent, cleanup, health, host_update, logger, mcp, mcp_probe, ports};
+use serde::Serialize;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tauri::api::path::resource_dir;
+use tauri::{Config, Env, PackageInfo};
+
+/// 命令行中是否带有 `--headless`
+pub fn requested() -> bool {
+    std::env::args().skip(1).any(|arg| arg == HEADLESS_FLAG)
+}
+
+/// 没有 webview 时的宿主：状态由自己持有，事件不发给前端
+#[derive(Clone)]
+pub struct Headless(Arc<HeadlessState>);
+
+struct HeadlessState {
+    config: Arc<Config>,
+    package_info: PackageInfo,
+    env: Env,
+    host_server: HostServerProcess,
+    logs: HostServerLogs,
+    port_conflicts: PortConflicts,
+    mcp_runtimes_missing: McpRuntimesMissing,
+    process_monitor: ProcessMonitor,
+}
+
+impl Headless {
+    pub fn new(config: Config, package_info: PackageInfo) -> Self {
+        Headless(Arc::new(HeadlessState {
+            config: Arc::new(config),
+            package_info,
+            env: Env::default(),
+            host_server: HostServerProcess(Mutex::new(HostServerState::default())),
+            logs: HostServerLogs::default(),
+            port_conflicts: PortConflicts(Mutex::new(Vec::new())),
+            mcp_runtimes_missing: McpRuntimesMissing(Mutex::new(Vec::new())),
+            process_monitor: ProcessMonitor::default(),
+        }))
+    }
+
+    /// host_server 每次 ready（包括重启后）都输出一次，密钥随重启变化
+    fn print_address(&self) {
+        let status = HostServerStatus::from(&*self.0.host_server.0.lock().unwrap());
+        let url = match (status.external_url, status.port) {
+            (Some(url), _) => url,
+            (None, Some(port)) => health::local_url(port),
+            (None, None) => return,
+        };
+        // 与外部 host_server 模式使用相同的变量名，脚本可以直接 eval；旧版 host_server 没有密钥
+        let mut stdout = std::io::stdout().lock();
+        let _ = writeln!(stdout, "{}={}", HOST_SERVER_EXTERNAL_URL_ENV, url);
+        if let Some(secret) = self.0.host_server.secret() {
+            let _ = writeln!(stdout, "{}={}", HOST_SERVER_SECRET_ENV, secret);
+        }
+        let _ = stdout.flush();
+    }
+}
+
+impl Host for Headless {
+    fn app_config(&self) -> Arc<Config> {
+        self.0.config.clone()
+    }
+
+    fn resource_dir(&self) -> Option<PathBuf> {
+        resource_dir(&self.0.package_info, &self.0.env)
+    }
+
+    fn emit_event<S: Serialize + Clone>(&self, event: &str, payload: S) -> Result<(), String> {
+        if event == HOST_SERVER_EVENT_NAME {
+            self.print_address();
+        } else {
+            log::debug!(
+                "{}: {}",
+                event,
+                serde_json::to_string(&payload).unwrap_or_default()
+            );
+        }
+        Ok(())
+    }
+
+    fn host_server(&self) -> &HostServerProcess {
+        &self.0.host_server
+    }
+
+    fn host_server_logs(&self) -> &HostServerLogs {
+        &self.0.logs
+    }
+
+    fn port_conflicts(&self) -> &PortConflicts {
+        &self.0.port_conflicts
+    }
+
+    fn mcp_runtimes_missing(&self) -> &McpRuntimesMissing {
+        &self.0.mcp_runtimes_missing
+    }
+
+    fn process_monitor(&self) -> &ProcessMonitor {
+        &self.0.process_monitor
+    }
+}
+
+/// 等待 SIGTERM 或 Ctrl-C
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = terminate.recv() => log::info!("Received SIGTERM"),
+                    _ = tokio::signal::ctrl_c() => log::info!("Received SIGINT"),
+                }
+                return;
+            }
+            Err(e) => log::warn!("Failed to listen for SIGTERM: {}", e),
+        }
+    }
+    let _ = tokio::signal::ctrl_c().await;
+    log::info!("Received Ctrl-C");
+}
+
+/// 与 GUI 相同的初始化流程，但不创建窗口；前台运行直到收到 SIGTERM，返回进程退出码
+pub async fn run(config: Config, package_info: PackageInfo, instance: Option<SingleInstance>) -> i32 {
+    logger::init_logger(&config, true);
+    log::info!("AidenAI started in headless mode");
+    // 监听线程持有单实例锁直到退出，记录其他实例转发的参数
+    if let Some(instance) = instance {
+        instance.listen_headless();
+    }
+
+    let host = Headless::new(config, package_info);
+    let config = host.app_config();
+    if let Err(e) = mcp::init_mcp_config(&host).and_then(|_| agent::init_agent_config(&host)) {
+        log::error!("Failed to init config: {}", e);
+        return 1;
+    }
+    cleanup::cleanup_database(&config);
+    if host_server::external_url().is_none() {
+        #[cfg(target_os = "linux")]
+        cleanup::sweep_orphans(&config);
+        ports::report_port_conflicts(&host, PORTS_TO_CHECK);
+    }
+    if let Err(e) = host_server::launch_host_server(&host, host.host_server()) {
+        log::error!("Failed to launch host_server: {}", e);
+        return 1;
+    }
+    tokio::spawn(mcp_probe::warn_missing_runtimes(host.clone()));
+    tokio::spawn(host_update::check_on_startup(host.clone()));
+    tokio::spawn(process_stats::monitor_thresholds(host.clone()));
+
+    // host_server 放弃重启时也不退出，保留日志和状态供排查，由调度方决定是否重启
+    shutdown_signal().await;
+
+    log::info!("Headless mode is exiting — now cleaning processes");
+    let stopping = host.clone();
+    let _ = tokio::task::spawn_blocking(move || crate::cleanup_processes(stopping.host_server()))
+        .await;
+    0
+}
@@ -0,0 +1,86 @@
+use crate::constants::{
+    HOST_SERVER_HEALTH_PATH, HOST_SERVER_READY_TIMEOUT_ENV, HOST_SERVER_READY_TIMEOUT_SECS,
+};
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+const PROBE_INTERVAL: Duration = Duration::from_millis(500);
+
+pub fn local_url(port: u16) -> String {
+    format!("http://127.0.0.1:{}", port)
+}
+
+/// ready 超时时间，可通过环境变量 AIDEN_HOST_SERVER_READY_TIMEOUT（秒）覆盖
+pub fn ready_timeout() -> Duration {
+    let secs = std::env::var(HOST_SERVER_READY_TIMEOUT_ENV)
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+        .unwrap_or(HOST_SERVER_READY_TIMEOUT_SECS);
+    Duration::from_secs(secs)
+}
+
+/// 健康检查使用的 client，一次等待过程中复用
+fn client() -> Result<reqwest::Client, String> {
+    reqwest::Client::builder()
+        .no_proxy()
+        .timeout(PROBE_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to build health check client: {}", e))
+}
+
+/// 探测一次：先确认端口可以建立 TCP 连接，再 GET 健康检查接口
+pub async fn probe(client: &reqwest::Client, base_url: &str) -> bool {
+    let url = match reqwest::Url::parse(base_url) {
+        Ok(url) => url,
+        Err(e) => {
+            log::error!("Invalid host_server url {}: {}", base_url, e);
+            return false;
+        }
+    };
+    let host = url.host_str().unwrap_or("127.0.0.1").to_string();
+    let port = url.port_or_known_default().unwrap_or(80);
+
+    match tokio::time::timeout(PROBE_TIMEOUT, TcpStream::connect((host.as_str(), port))).await {
+        Ok(Ok(_)) => {}
+        _ => return false,
+    }
+
+    let health_url = format!(
+        "{}{}",
+        base_url.trim_end_matches('/'),
+        HOST_SERVER_HEALTH_PATH
+    );
+    match client.get(&health_url).send().await {
+        Ok(resp) => resp.status().is_success(),
+        Err(_) => false,
+    }
+}
+
+/// 轮询健康检查直到成功或超时，返回是否 ready
+///
+/// already_ready 返回 true（如 stdout 快速路径已确认 ready）时立即停止轮询。
+pub async fn wait_until_ready(
+    base_url: &str,
+    timeout: Duration,
+    already_ready: impl Fn() -> bool,
+) -> bool {
+    let client = match client() {
+        Ok(client) => client,
+        Err(e) => {
+            log::error!("{}", e);
+            return false;
+        }
+    };
+    let deadline = Instant::now() + timeout;
+    loop {
+        if already_ready() || probe(&client, base_url).await {
+            return true;
+        }
+        if Instant::now() >= deadline {
+            return false;
+        }
+        tokio::time::sleep(PROBE_INTERVAL).await;
+    }
+}
@@ -0,0 +1,67 @@
+use crate::host_log::HostServerLogs;
+use crate::host_server::HostServerProcess;
+use crate::mcp_probe::McpRuntimesMissing;
+use crate::ports::PortConflicts;
+use crate::process_stats::ProcessMonitor;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tauri::api::path::resource_dir;
+use tauri::{AppHandle, Config, Manager, Runtime};
+
+/// host_server 监督、日志、更新等后台任务依赖的宿主能力
+///
+/// GUI 下由 AppHandle 提供；`--headless` 没有 webview 和 Tauri 事件循环，由 headless::Headless 提供。
+pub trait Host: Clone + Send + Sync + 'static {
+    fn app_config(&self) -> Arc<Config>;
+
+    /// 打包资源目录（生产环境的 resources/bin、host_server 等）
+    fn resource_dir(&self) -> Option<PathBuf>;
+
+    /// 通知前端；headless 下没有前端，由实现决定如何输出
+    fn emit_event<S: Serialize + Clone>(&self, event: &str, payload: S) -> Result<(), String>;
+
+    fn host_server(&self) -> &HostServerProcess;
+
+    fn host_server_logs(&self) -> &HostServerLogs;
+
+    fn port_conflicts(&self) -> &PortConflicts;
+
+    fn mcp_runtimes_missing(&self) -> &McpRuntimesMissing;
+
+    fn process_monitor(&self) -> &ProcessMonitor;
+}
+
+impl<R: Runtime> Host for AppHandle<R> {
+    fn app_config(&self) -> Arc<Config> {
+        self.config()
+    }
+
+    fn resource_dir(&self) -> Option<PathBuf> {
+        resource_dir(self.package_info(), &self.env())
+    }
+
+    fn emit_event<S: Serialize + Clone>(&self, event: &str, payload: S) -> Result<(), String> {
+        self.emit_all(event, payload).map_err(|e| e.to_string())
+    }
+
+    fn host_server(&self) -> &HostServerProcess {
+        self.state::<HostServerProcess>().inner()
+    }
+
+    fn host_server_logs(&self) -> &HostServerLogs {
+        self.state::<HostServerLogs>().inner()
+    }
+
+    fn port_conflicts(&self) -> &PortConflicts {
+        self.state::<PortConflicts>().inner()
+    }
+
+    fn mcp_runtimes_missing(&self) -> &McpRuntimesMissing {
+        self.state::<McpRuntimesMissing>().inner()
+    }
+
+    fn process_monitor(&self) -> &ProcessMonitor {
+        self.state::<ProcessMonitor>().inner()
+    }
+}
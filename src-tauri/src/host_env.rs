@@ -0,0 +1,386 @@
+use crate::constants::HOST_SERVER_SECRET_ENV;
+use crate::host::Host;
+use crate::{config, host_server, mcp};
+use crate::mcp_server::McpTransport;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::api::path::app_data_dir;
+use tauri::{AppHandle, Config};
+
+/// 键名按 `_` 分段后，某一段是这些词（或其复数）的变量视为敏感信息
+const SECRET_KEY_MARKERS: &[&str] = &[
+    "KEY",
+    "TOKEN",
+    "SECRET",
+    "PASSWORD",
+    "PASSWD",
+    "CREDENTIAL",
+    "AUTH",
+    "SESSION",
+    "COOKIE",
+    "DSN",
+];
+
+/// 值以这些前缀开头时视为敏感信息（常见 API key 格式）
+const SECRET_VALUE_PREFIXES: &[&str] = &["sk-", "ghp_", "gho_", "github_pat_", "xox", "AKIA", "eyJ"];
+
+/// 从 GUI 进程环境继承给 host_server（进而传给所有 MCP server）的变量，其余一律不传。
+///
+/// 包括：用户与临时目录、语言区域、代理、桌面会话（浏览器类 MCP 需要），以及 Windows 运行必需项。
+/// PATH 由 append_bin_to_path 单独构造；用户可在 Config/env.allowlist.json 中追加变量名。
+const INHERITED_ENV: &[&str] = &[
+    "HOME",
+    "USER",
+    "LOGNAME",
+    "SHELL",
+    "TMPDIR",
+    "TEMP",
+    "TMP",
+    "LANG",
+    "LANGUAGE",
+    "TZ",
+    "HTTP_PROXY",
+    "HTTPS_PROXY",
+    "ALL_PROXY",
+    "NO_PROXY",
+    "DISPLAY",
+    "WAYLAND_DISPLAY",
+    "XAUTHORITY",
+    "XDG_RUNTIME_DIR",
+    "DBUS_SESSION_BUS_ADDRESS",
+    "SYSTEMROOT",
+    "SYSTEMDRIVE",
+    "WINDIR",
+    "COMSPEC",
+    "PATHEXT",
+    "USERPROFILE",
+    "HOMEDRIVE",
+    "HOMEPATH",
+    "APPDATA",
+    "LOCALAPPDATA",
+    "PROGRAMDATA",
+    "PROGRAMFILES",
+    "PROGRAMFILES(X86)",
+    "NUMBER_OF_PROCESSORS",
+    "PROCESSOR_ARCHITECTURE",
+];
+
+/// 以这些前缀开头的变量同样继承（LC_ALL、LC_CTYPE 等）
+const INHERITED_ENV_PREFIXES: &[&str] = &["LC_"];
+
+/// 打包的 .env 中允许传给 host_server 的变量（镜像源和 host_server 版本，见 release.yml）。
+///
+/// 开发环境的 ../.env 同时存放 GITHUB_TOKEN 等下载脚本用的变量，不在此列且不在允许列表中的一律不传。
+const DOTENV_KEYS: &[&str] = &[
+    "NPM_CONFIG_REGISTRY",
+    "UV_INDEX",
+    "UV_DEFAULT_INDEX",
+    "UV_EXTRA_INDEX_URL",
+    "HOST_SERVER_VERSION",
+];
+
+/// 打包的 .env：开发环境为 ../.env，生产环境为 resources/bin/.env
+pub fn get_env_path<H: Host>(app: &H) -> Option<PathBuf> {
+    if cfg!(debug_assertions) {
+        Some(
+            std::env::current_dir()
+                .ok()
+                .map(|dir: PathBuf| dir.join("../.env"))?,
+        )
+    } else {
+        app.resource_dir().map(|dir: PathBuf| dir.join("bin/.env"))
+    }
+}
+
+/// 用户可编辑的环境变量覆盖文件
+/// eg: ~/Library/Application Support/com.aiden.chat/Config/env.config.json
+pub fn get_overrides_path(config: &Config) -> Option<PathBuf> {
+    let mut path: PathBuf = app_data_dir(config)?;
+    path.push("Config");
+    std::fs::create_dir_all(&path).ok()?;
+    path.push("env.config.json");
+    Some(path)
+}
+
+/// 用户批准额外继承的变量名
+/// eg: ~/Library/Application Support/com.aiden.chat/Config/env.allowlist.json
+pub fn get_allowlist_path(config: &Config) -> Option<PathBuf> {
+    let mut path: PathBuf = app_data_dir(config)?;
+    path.push("Config");
+    std::fs::create_dir_all(&path).ok()?;
+    path.push("env.allowlist.json");
+    Some(path)
+}
+
+pub fn load_allowlist(config: &Config) -> Vec<String> {
+    let path = match get_allowlist_path(config) {
+        Some(path) if path.exists() => path,
+        _ => return Vec::new(),
+    };
+    match fs::read_to_string(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|text| serde_json::from_str(&text).map_err(|e| e.to_string()))
+    {
+        Ok(names) => names,
+        Err(e) => {
+            log::warn!("Ignoring invalid env allow-list {:?}: {}", path, e);
+            Vec::new()
+        }
+    }
+}
+
+/// 变量名是否允许从 GUI 进程环境继承；代理变量常见小写形式，统一按大写比较
+pub fn is_inherited(key: &str, extras: &[String]) -> bool {
+    let upper = key.to_uppercase();
+    INHERITED_ENV.contains(&upper.as_str())
+        || INHERITED_ENV_PREFIXES
+            .iter()
+            .any(|prefix| upper.starts_with(prefix))
+        || extras.iter().any(|extra| extra.to_uppercase() == upper)
+}
+
+/// 读取 .env 文件，文件不存在时返回空
+pub fn load_dotenv(path: &Path) -> BTreeMap<String, String> {
+    let mut vars = BTreeMap::new();
+    if !path.exists() {
+        log::info!("No .env file at {:?}", path);
+        return vars;
+    }
+    match dotenvy::from_path_iter(path) {
+        Ok(iter) => {
+            for item in iter {
+                match item {
+                    Ok((key, value)) => {
+                        vars.insert(key, value);
+                    }
+                    Err(e) => log::warn!("Skipping invalid line in {:?}: {}", path, e),
+                }
+            }
+            log::info!("Loaded {} variable(s) from {:?}", vars.len(), path);
+        }
+        Err(e) => log::warn!("Failed to read {:?}: {}", path, e),
+    }
+    vars
+}
+
+pub fn load_overrides(config: &Config) -> BTreeMap<String, String> {
+    let path = match get_overrides_path(config) {
+        Some(path) if path.exists() => path,
+        _ => return BTreeMap::new(),
+    };
+    match fs::read_to_string(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|text| serde_json::from_str(&text).map_err(|e| e.to_string()))
+    {
+        Ok(overrides) => overrides,
+        Err(e) => {
+            log::warn!("Ignoring invalid env overrides {:?}: {}", path, e);
+            BTreeMap::new()
+        }
+    }
+}
+
+/// 按键名分段或值的前缀判断是否敏感；INHERITED_ENV 中的系统变量（XAUTHORITY、DBUS_SESSION_BUS_ADDRESS 等）不按键名判断
+pub fn is_secret(key: &str, value: &str) -> bool {
+    let upper = key.to_uppercase();
+    let key_is_secret = !INHERITED_ENV.contains(&upper.as_str())
+        && upper.split('_').any(|segment| {
+            SECRET_KEY_MARKERS.iter().any(|marker| {
+                segment == *marker || segment.strip_suffix('S') == Some(*marker)
+            })
+        });
+    key_is_secret
+        || SECRET_VALUE_PREFIXES
+            .iter()
+            .any(|prefix| value.starts_with(prefix))
+}
+
+/// 日志中隐藏敏感值，只保留长度
+pub fn redact(key: &str, value: &str) -> String {
+    if is_secret(key, value) {
+        format!("<redacted, {} chars>", value.chars().count())
+    } else {
+        value.to_string()
+    }
+}
+
+/// .env 中只保留 DOTENV_KEYS 和允许继承的变量
+fn filter_dotenv(dotenv: &mut BTreeMap<String, String>, extras: &[String]) {
+    dotenv.retain(|key, _| {
+        let allowed = DOTENV_KEYS.contains(&key.as_str()) || is_inherited(key, extras);
+        if !allowed {
+            log::info!("Withholding {} (.env) from host_server: not in the allow-list", key);
+        }
+        allowed
+    });
+}
+
+/// 构造 host_server 的环境变量，优先级从低到高：允许继承的进程环境、PATH、.env、用户覆盖
+///
+/// .env 同样只取 DOTENV_KEYS 和允许列表中的变量；用户覆盖是用户明确填写的值，原样传入。
+/// host_server 需以 env_clear 启动，否则仍会继承 GUI 的完整环境。
+pub fn build_host_server_env<H: Host>(
+    app: &H,
+    path_env: &str,
+) -> BTreeMap<String, String> {
+    let extras = load_allowlist(&app.app_config());
+    let mut vars: BTreeMap<String, String> = BTreeMap::new();
+    let mut withheld = 0;
+    for (key, value) in std::env::vars() {
+        if is_inherited(&key, &extras) {
+            vars.insert(key, value);
+        } else {
+            withheld += 1;
+        }
+    }
+    log::info!(
+        "Inheriting {} environment variable(s) into host_server, withholding {}",
+        vars.len(),
+        withheld
+    );
+    vars.insert("PATH".into(), path_env.to_string());
+    log::info!("Setting PATH to host_server: {}", path_env);
+
+    let mut dotenv = get_env_path(app)
+        .map(|path| load_dotenv(&path))
+        .unwrap_or_default();
+    filter_dotenv(&mut dotenv, &extras);
+    let overrides = load_overrides(&app.app_config());
+    for (source, layer) in [(".env", dotenv), ("user override", overrides)] {
+        for (key, value) in layer {
+            log::info!(
+                "Setting {} to host_server ({}): {}",
+                key,
+                source,
+                redact(&key, &value)
+            );
+            vars.insert(key, value);
+        }
+    }
+    // 授权密钥只经 stdin 交给 host_server，不能通过环境变量传给 MCP server
+    vars.remove(HOST_SERVER_SECRET_ENV);
+    vars
+}
+
+/// 读取用户批准额外继承的变量名
+#[tauri::command]
+pub fn read_env_allowlist(app: AppHandle) -> Vec<String> {
+    load_allowlist(&app.config())
+}
+
+/// 写入用户批准额外继承的变量名，重启 host_server 后生效
+#[tauri::command]
+pub fn write_env_allowlist(app: AppHandle, names: Vec<String>) -> Result<(), String> {
+    if let Some(name) = names
+        .iter()
+        .find(|name| name.is_empty() || name.contains('=') || name.contains('\0'))
+    {
+        return Err(format!("Invalid environment variable name: {:?}", name));
+    }
+    let path = get_allowlist_path(&app.config()).ok_or("配置路径不存在")?;
+    config::write_config_json(&path, &names)
+}
+
+/// 每个 MCP server 最终得到的环境变量（host_server 的环境 + mcp.config.json 中该 server 的 env），敏感值已隐藏
+#[tauri::command]
+pub async fn get_mcp_server_envs(
+    app: AppHandle,
+) -> Result<BTreeMap<String, BTreeMap<String, String>>, String> {
+    let path = mcp::get_user_config_path(&app.config()).ok_or("配置路径不存在")?;
+    let config = mcp::load_mcp_config(&path)?;
+    let base = host_server::effective_env(&app).await?;
+
+    let mut result = BTreeMap::new();
+    for (name, server) in &config.mcpServers {
+        let mut env = base.clone();
+        if let McpTransport::Stdio { env: extra, .. } = &server.transport {
+            env.extend(extra.iter().map(|(key, value)| (key.clone(), value.clone())));
+        }
+        let redacted = env
+            .iter()
+            .map(|(key, value)| (key.clone(), redact(key, value)))
+            .collect();
+        result.insert(name.clone(), redacted);
+    }
+    Ok(result)
+}
+
+/// 读取用户环境变量覆盖
+#[tauri::command]
+pub fn read_env_overrides(app: AppHandle) -> Result<BTreeMap<String, String>, String> {
+    let path = get_overrides_path(&app.config()).ok_or("配置路径不存在")?;
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let contents = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&contents).map_err(|e| e.to_string())
+}
+
+/// 写入用户环境变量覆盖，重启 host_server 后生效
+#[tauri::command]
+pub fn write_env_overrides(
+    app: AppHandle,
+    overrides: BTreeMap<String, String>,
+) -> Result<(), String> {
+    if let Some(key) = overrides
+        .keys()
+        .find(|key| key.is_empty() || key.contains('=') || key.contains('\0'))
+    {
+        return Err(format!("Invalid environment variable name: {:?}", key));
+    }
+    let path = get_overrides_path(&app.config()).ok_or("配置路径不存在")?;
+    config::write_config_json(&path, &overrides)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inherits_listed_and_allowed_names() {
+        let extras = vec!["my_tool_home".to_string()];
+        assert!(is_inherited("HOME", &extras));
+        assert!(is_inherited("https_proxy", &extras));
+        assert!(is_inherited("LC_ALL", &extras));
+        assert!(is_inherited("MY_TOOL_HOME", &extras));
+        assert!(!is_inherited("OPENAI_API_KEY", &extras));
+        assert!(!is_inherited("LCX", &extras));
+    }
+
+    #[test]
+    fn secrets_match_whole_segments() {
+        assert!(is_secret("OPENAI_API_KEY", "x"));
+        assert!(is_secret("github_token", "x"));
+        assert!(is_secret("AWS_SECRETS", "x"));
+        assert!(is_secret("SENTRY_DSN", "x"));
+        assert!(is_secret("ANYTHING", "sk-123"));
+        // 片段只是词的一部分，或是系统会话变量
+        assert!(!is_secret("KEYBOARD_LAYOUT", "us"));
+        assert!(!is_secret("AUTHOR", "me"));
+        assert!(!is_secret("XAUTHORITY", "/run/user/1000/xauth"));
+        assert!(!is_secret("DBUS_SESSION_BUS_ADDRESS", "unix:path=/run/user/1000/bus"));
+
+        assert_eq!(redact("API_KEY", "abcd"), "<redacted, 4 chars>");
+        assert_eq!(redact("DISPLAY", ":0"), ":0");
+    }
+
+    #[test]
+    fn dotenv_keeps_only_known_and_allowed_keys() {
+        let mut dotenv: BTreeMap<String, String> = [
+            ("NPM_CONFIG_REGISTRY", "https://registry.npmmirror.com"),
+            ("HOST_SERVER_VERSION", "v0.2.41"),
+            ("GITHUB_TOKEN", "ghp_x"),
+            ("HTTPS_PROXY", "http://127.0.0.1:7890"),
+            ("EXTRA", "1"),
+        ]
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+        filter_dotenv(&mut dotenv, &["EXTRA".to_string()]);
+        assert_eq!(
+            dotenv.keys().map(String::as_str).collect::<Vec<_>>(),
+            ["EXTRA", "HOST_SERVER_VERSION", "HTTPS_PROXY", "NPM_CONFIG_REGISTRY"]
+        );
+    }
+}
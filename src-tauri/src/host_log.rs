@@ -0,0 +1,368 @@
+use crate::constants::{HOST_SERVER_LOG_BUFFER_LINES, HOST_SERVER_LOG_EVENT};
+use crate::host::Host;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::State;
+
+/// host_server 输出的来源
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+/// 解析后的一条 host_server 日志
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogRecord {
+    pub level: log::Level,
+    /// Python 的 CRITICAL / FATAL，log crate 没有对应级别，按 Error 输出
+    pub critical: bool,
+    /// Python logger 名，如 uvicorn.error；未知时为空
+    pub logger: String,
+    pub message: String,
+}
+
+impl LogRecord {
+    /// 只有真正的 ERROR / CRITICAL 才计入崩溃诊断和错误上报
+    pub fn is_error(&self) -> bool {
+        self.level == log::Level::Error
+    }
+
+    /// 转发到 log 时使用的 target，如 host_server::uvicorn.error
+    pub fn target(&self) -> String {
+        if self.logger.is_empty() {
+            "host_server".to_string()
+        } else {
+            format!("host_server::{}", self.logger)
+        }
+    }
+}
+
+/// 返回 (级别, 是否 CRITICAL)
+fn parse_level(level: &str) -> Option<(log::Level, bool)> {
+    match level.trim().to_uppercase().as_str() {
+        "TRACE" => Some((log::Level::Trace, false)),
+        "DEBUG" => Some((log::Level::Debug, false)),
+        "INFO" => Some((log::Level::Info, false)),
+        "WARNING" | "WARN" => Some((log::Level::Warn, false)),
+        "ERROR" | "EXCEPTION" => Some((log::Level::Error, false)),
+        "CRITICAL" | "FATAL" => Some((log::Level::Error, true)),
+        _ => None,
+    }
+}
+
+/// JSON 行：兼容 python-json-logger、structlog 等常见字段名
+fn parse_json(line: &str) -> Option<LogRecord> {
+    if !line.starts_with('{') {
+        return None;
+    }
+    let value: Value = serde_json::from_str(line).ok()?;
+    let field = |names: &[&str]| {
+        names
+            .iter()
+            .find_map(|name| value.get(*name).and_then(Value::as_str))
+            .map(String::from)
+    };
+    let (level, critical) = parse_level(&field(&["levelname", "level", "severity"])?)?;
+    let mut message = field(&["message", "msg", "event"]).unwrap_or_default();
+    if let Some(exc) = field(&["exc_info", "exception", "stack_info"]) {
+        message = format!("{}\n{}", message, exc);
+    }
+    Some(LogRecord {
+        level,
+        critical,
+        logger: field(&["name", "logger", "logger_name"]).unwrap_or_default(),
+        message,
+    })
+}
+
+/// logging 默认格式 `LEVEL:name:msg`，以及 uvicorn 的 `LEVEL:     msg`
+fn parse_prefixed(line: &str) -> Option<LogRecord> {
+    let (level, rest) = line.split_once(':')?;
+    // Python 的级别名总是大写，避免把 "Error: ..." 之类的普通文本当成日志记录
+    if level.is_empty() || !level.chars().all(|c| c.is_ascii_uppercase()) {
+        return None;
+    }
+    let (level, critical) = parse_level(level)?;
+    // uvicorn 在级别后用空格对齐，没有 logger 名
+    if rest.starts_with(' ') {
+        return Some(LogRecord {
+            level,
+            critical,
+            logger: "uvicorn".to_string(),
+            message: rest.trim().to_string(),
+        });
+    }
+    let (logger, message) = rest.split_once(':').unwrap_or(("", rest));
+    Some(LogRecord {
+        level,
+        critical,
+        logger: logger.to_string(),
+        message: message.to_string(),
+    })
+}
+
+/// traceback 结尾的异常行，如 `ValueError: ...`、`httpx.ConnectError: ...`
+fn is_exception_line(line: &str) -> bool {
+    let name = match line.split_once(": ") {
+        Some((name, _)) => name,
+        None => return false,
+    };
+    let class = name.rsplit('.').next().unwrap_or_default();
+    name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+        && class.starts_with(|c: char| c.is_ascii_uppercase())
+}
+
+/// 逐行解析 host_server 输出；缩进的行（如 traceback 的后续行）沿用上一条记录的级别
+#[derive(Default)]
+pub struct LogParser {
+    last: Option<(log::Level, bool, String)>,
+}
+
+impl LogParser {
+    pub fn parse(&mut self, line: &str, stream: Stream) -> LogRecord {
+        if let Some(record) = parse_json(line).or_else(|| parse_prefixed(line)) {
+            self.last = Some((record.level, record.critical, record.logger.clone()));
+            return record;
+        }
+        if line.starts_with("Traceback (most recent call last)") {
+            self.last = Some((log::Level::Error, false, String::new()));
+            return LogRecord {
+                level: log::Level::Error,
+                critical: false,
+                logger: String::new(),
+                message: line.to_string(),
+            };
+        }
+        let continuation = line.starts_with(' ') || line.starts_with('\t') || line.is_empty();
+        match self.last.clone() {
+            Some((level, critical, logger))
+                if continuation || (level == log::Level::Error && is_exception_line(line)) =>
+            {
+                // traceback 以不缩进的异常行（如 ValueError: ...）结束
+                if !continuation {
+                    self.last = None;
+                }
+                LogRecord {
+                    level,
+                    critical,
+                    logger,
+                    message: line.to_string(),
+                }
+            }
+            _ => {
+                self.last = None;
+                LogRecord {
+                    // 未知格式的 stderr 不一定是错误（Python 默认把 INFO 也写到 stderr）
+                    level: match stream {
+                        Stream::Stdout => log::Level::Info,
+                        Stream::Stderr => log::Level::Warn,
+                    },
+                    critical: false,
+                    logger: String::new(),
+                    message: line.to_string(),
+                }
+            }
+        }
+    }
+}
+
+/// 按解析出的级别和 target 写入应用日志
+pub fn emit(record: &LogRecord) {
+    let target = record.target();
+    if record.critical {
+        log::log!(target: &target, record.level, "CRITICAL {}", record.message);
+    } else {
+        log::log!(target: &target, record.level, "{}", record.message);
+    }
+}
+
+/// 环形缓冲中的一行，也是 host_server_log 事件的负载
+#[derive(Debug, Clone, Serialize)]
+pub struct LogEntry {
+    /// 单调递增，前端用 since 增量拉取
+    pub seq: u64,
+    /// unix 时间戳（毫秒）
+    pub timestamp: u64,
+    pub stream: Stream,
+    /// trace / debug / info / warn / error
+    pub level: String,
+    pub critical: bool,
+    pub logger: String,
+    pub message: String,
+}
+
+/// 最近的 host_server 输出，跨 host_server 重启保留
+#[derive(Default)]
+pub struct HostServerLogs(pub Mutex<LogBuffer>);
+
+#[derive(Default)]
+pub struct LogBuffer {
+    entries: VecDeque<LogEntry>,
+    next_seq: u64,
+    /// 前端打开日志页时才逐行推送事件，其余时间只写缓冲
+    watching: bool,
+}
+
+/// 写入环形缓冲；前端订阅时推送给前端
+pub fn record<H: Host>(app: &H, stream: Stream, record: &LogRecord) {
+    let entry = {
+        let mut buffer = app.host_server_logs().0.lock().unwrap();
+        buffer.next_seq += 1;
+        let entry = LogEntry {
+            seq: buffer.next_seq,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0),
+            stream,
+            level: record.level.as_str().to_lowercase(),
+            critical: record.critical,
+            logger: record.logger.clone(),
+            message: record.message.clone(),
+        };
+        if buffer.entries.len() >= HOST_SERVER_LOG_BUFFER_LINES {
+            buffer.entries.pop_front();
+        }
+        buffer.entries.push_back(entry.clone());
+        if !buffer.watching {
+            return;
+        }
+        entry
+    };
+    // 失败只记 debug，避免日志自我放大
+    if let Err(e) = app.emit_event(HOST_SERVER_LOG_EVENT, entry) {
+        log::debug!("Failed to emit {}: {}", HOST_SERVER_LOG_EVENT, e);
+    }
+}
+
+/// 开启或关闭 host_server_log 事件推送；开启后先用 get_host_server_logs 补齐之前的输出
+#[tauri::command]
+pub fn watch_host_server_logs(state: State<'_, HostServerLogs>, enabled: bool) {
+    state.0.lock().unwrap().watching = enabled;
+    log::info!(
+        "{} host_server log events",
+        if enabled { "Started" } else { "Stopped" }
+    );
+}
+
+/// 最近的 host_server 输出；since 为上次拿到的 seq，level 为最低级别（critical 表示只看 CRITICAL）
+#[tauri::command]
+pub fn get_host_server_logs(
+    state: State<'_, HostServerLogs>,
+    since: Option<u64>,
+    level: Option<String>,
+) -> Result<Vec<LogEntry>, String> {
+    let critical_only = level
+        .as_deref()
+        .map(|level| level.eq_ignore_ascii_case("critical"))
+        .unwrap_or(false);
+    let min_level = match level.as_deref() {
+        Some(level) if !critical_only => Some(
+            log::Level::from_str(if level.eq_ignore_ascii_case("warning") {
+                "warn"
+            } else {
+                level
+            })
+            .map_err(|_| format!("Unknown log level: {}", level))?,
+        ),
+        _ => None,
+    };
+    let since = since.unwrap_or(0);
+    let buffer = state.0.lock().unwrap();
+    Ok(buffer
+        .entries
+        .iter()
+        .filter(|entry| entry.seq > since)
+        .filter(|entry| !critical_only || entry.critical)
+        .filter(|entry| {
+            min_level
+                .map(|min| {
+                    log::Level::from_str(&entry.level)
+                        .map(|level| level <= min)
+                        .unwrap_or(true)
+                })
+                .unwrap_or(true)
+        })
+        .cloned()
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_all(lines: &[&str], stream: Stream) -> Vec<log::Level> {
+        let mut parser = LogParser::default();
+        lines
+            .iter()
+            .map(|line| parser.parse(line, stream).level)
+            .collect()
+    }
+
+    #[test]
+    fn parses_prefixed_formats() {
+        let record = parse_prefixed("WARNING:app.mcp:server slow").unwrap();
+        assert_eq!(record.level, log::Level::Warn);
+        assert_eq!(record.logger, "app.mcp");
+        assert_eq!(record.message, "server slow");
+
+        let record = parse_prefixed("INFO:     Started server process [42]").unwrap();
+        assert_eq!(record.level, log::Level::Info);
+        assert_eq!(record.logger, "uvicorn");
+        assert_eq!(record.message, "Started server process [42]");
+
+        let record = parse_prefixed("CRITICAL:root:boom").unwrap();
+        assert!(record.critical && record.is_error());
+
+        assert!(parse_prefixed("Error: not a log record").is_none());
+        assert!(parse_prefixed("NOTICE:root:unknown level").is_none());
+        assert!(parse_prefixed("no colon at all").is_none());
+    }
+
+    #[test]
+    fn plain_line_after_error_is_not_error() {
+        let levels = parse_all(
+            &["ERROR:app:request failed", "listening for MCP clients"],
+            Stream::Stderr,
+        );
+        assert_eq!(levels, [log::Level::Error, log::Level::Warn]);
+    }
+
+    #[test]
+    fn traceback_ends_with_exception_line() {
+        let levels = parse_all(
+            &[
+                "ERROR:app:request failed",
+                "Traceback (most recent call last):",
+                "  File \"app.py\", line 1, in <module>",
+                "",
+                "httpx.ConnectError: connection refused",
+                "next plain line",
+            ],
+            Stream::Stdout,
+        );
+        assert_eq!(
+            levels,
+            [
+                log::Level::Error,
+                log::Level::Error,
+                log::Level::Error,
+                log::Level::Error,
+                log::Level::Error,
+                log::Level::Info,
+            ]
+        );
+    }
+
+    #[test]
+    fn indented_lines_keep_level() {
+        let levels = parse_all(&["WARNING:app:slow", "  details"], Stream::Stdout);
+        assert_eq!(levels, [log::Level::Warn, log::Level::Warn]);
+    }
+}
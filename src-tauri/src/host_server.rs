@@ -0,0 +1,1183 @@
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+#[cfg(target_os = "windows")]
+use crate::constants::CREATE_NO_WINDOW;
+
+use crate::constants::{
+    HOST_SERVER_DEV_PORT, HOST_SERVER_DEV_PORT_ENV, HOST_SERVER_EVENT_NAME,
+    HOST_SERVER_EXTERNAL_URL_ENV, HOST_SERVER_EXTERNAL_URL_FLAG,
+    HOST_SERVER_EXITED_EVENT, HOST_SERVER_FAILED_EVENT, HOST_SERVER_MAX_RESTARTS,
+    HOST_SERVER_PORT_IN_USE_PATTERNS, HOST_SERVER_PORT_RETRIES, HOST_SERVER_READY_TEXT, HOST_SERVER_RESTARTING_EVENT,
+    HOST_SERVER_RESTART_BACKOFF_MAX_MS, HOST_SERVER_RESTART_BACKOFF_MS, HOST_SERVER_SECRET_BYTES,
+    HOST_SERVER_SHUTDOWN_GRACE, HOST_SERVER_STABLE_UPTIME_SECS, HOST_SERVER_STDERR_TAIL_LINES,
+    HOST_SERVER_STDIN_SECRET_VERSION, HOST_SERVER_STOP_TIMEOUT,
+    HOST_SERVER_TREE_REFRESH,
+};
+use crate::host::Host;
+#[cfg(target_os = "linux")]
+use crate::process::ProcessInfo;
+#[cfg(target_os = "linux")]
+use crate::run_state;
+use crate::{agent, health, host_env, host_log, host_update, integrity, mcp, process, shell_env};
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::process::{Command as StdCommand, Stdio as StdStdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Once};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, State, Window};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+use tokio::process::{Child, Command as TokioCommand};
+use tokio::sync::mpsc;
+use tokio::task;
+
+pub struct HostServerProcess(pub Mutex<HostServerState>);
+
+/// host_server 当前运行状态，由 supervisor 任务维护
+#[derive(Default)]
+pub struct HostServerState {
+    pub pid: Option<u32>,
+    pub port: Option<u16>,
+    pub started_at: Option<Instant>,
+    pub ready: bool,
+    pub restart_count: u32,
+    pub last_exit_code: Option<i32>,
+    /// 外部 host_server 模式下的地址
+    pub external_url: Option<String>,
+    /// 正在检查或应用独立更新，见 host_update::check_and_apply
+    pub updating: bool,
+    /// 当前这次启动的授权密钥，只通过 get_host_server_secret 交给主窗口；
+    /// 旧版 host_server 或外部模式未提供密钥时为 None，前端改用 /authorization/token
+    secret: Option<String>,
+    launch: Option<Arc<LaunchConfig>>,
+    /// 同一时间只解析一次 launch，supervise 与 effective_env 并发调用时后到的等待结果
+    resolving: Arc<tokio::sync::Mutex<()>>,
+    supervisor: Option<mpsc::UnboundedSender<SupervisorCommand>>,
+}
+
+impl HostServerProcess {
+    /// 当前（或最近一次）启动 host_server 使用的环境变量
+    pub fn launch_env(&self) -> Option<BTreeMap<String, String>> {
+        self.0
+            .lock()
+            .unwrap()
+            .launch
+            .as_ref()
+            .map(|launch| launch.env.clone())
+    }
+
+    /// 当前这次启动的授权密钥；host_server 未运行时为 None
+    pub fn secret(&self) -> Option<String> {
+        self.0.lock().unwrap().secret.clone()
+    }
+}
+
+/// host_server 会得到的环境变量；与启动共用同一份 launch，尚未解析时在阻塞线程中解析（可能要运行登录 shell）
+pub async fn effective_env<H: Host>(app: &H) -> Result<BTreeMap<String, String>, String> {
+    launch_config(app).await.map(|launch| launch.env.clone())
+}
+
+impl HostServerState {
+    fn clear_process(&mut self) {
+        self.pid = None;
+        self.port = None;
+        self.started_at = None;
+        self.ready = false;
+        self.secret = None;
+    }
+}
+
+/// host_server_status 命令的返回值
+#[derive(Clone, Serialize)]
+pub struct HostServerStatus {
+    pub running: bool,
+    pub ready: bool,
+    pub pid: Option<u32>,
+    pub port: Option<u16>,
+    pub uptime_secs: Option<u64>,
+    pub restart_count: u32,
+    pub last_exit_code: Option<i32>,
+    pub external_url: Option<String>,
+}
+
+impl From<&HostServerState> for HostServerStatus {
+    fn from(state: &HostServerState) -> Self {
+        HostServerStatus {
+            running: state.supervisor.is_some(),
+            ready: state.ready,
+            pid: state.pid,
+            port: state.port,
+            uptime_secs: state.started_at.map(|t| t.elapsed().as_secs()),
+            restart_count: state.restart_count,
+            last_exit_code: state.last_exit_code,
+            external_url: state.external_url.clone(),
+        }
+    }
+}
+
+enum SupervisorCommand {
+    /// 停止 host_server 并退出 supervisor，完成后通过 sender 通知
+    Stop(std::sync::mpsc::Sender<()>),
+}
+
+#[derive(Clone, Serialize)]
+struct HostServerExited {
+    pid: Option<u32>,
+    port: u16,
+    code: Option<i32>,
+    uptime_secs: u64,
+}
+
+#[derive(Clone, Serialize)]
+struct HostServerRestarting {
+    attempt: u32,
+    delay_ms: u64,
+}
+
+/// host_server_ready 事件的负载；url 为完整的基础地址（外部 host_server 可能不在本机）
+#[derive(Clone, Serialize)]
+pub struct HostServerReady {
+    pub port: u16,
+    pub url: String,
+}
+
+/// host_server_failed 事件的负载，前端按 kind 区分失败原因
+#[derive(Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum HostServerFailed {
+    /// 进程未能启动
+    SpawnFailed { message: String },
+    /// 二进制与清单不符（sha256 或版本），不会启动
+    VerificationFailed(integrity::VerifyError),
+    /// 超时仍未通过健康检查
+    ReadyTimeout {
+        port: u16,
+        timeout_secs: u64,
+        stderr_tail: Vec<String>,
+    },
+    /// 端口被其他进程抢占，换端口重试后仍失败（开发模式下不重试）
+    PortInUse {
+        port: u16,
+        retries: u32,
+        stderr_tail: Vec<String>,
+    },
+    /// 连续崩溃超过上限，不再自动重启
+    CrashLoop {
+        restarts: u32,
+        stderr_tail: Vec<String>,
+    },
+}
+
+/// 单次启动的 ready 状态，stdout 快速路径与健康检查共享，保证 ready 只发一次
+#[derive(Default)]
+struct Readiness {
+    ready: AtomicBool,
+    /// ready 之前 host_server 自身报告端口被占用
+    port_in_use: AtomicBool,
+    stderr_tail: Mutex<VecDeque<String>>,
+}
+
+impl Readiness {
+    fn mark_ready<H: Host>(&self, app: &H, port: u16) {
+        if !self.ready.swap(true, Ordering::SeqCst) {
+            log::info!("host_server is ready on port {}", port);
+            app.host_server().0.lock().unwrap().ready = true;
+            let url = health::local_url(port);
+            emit(app, HOST_SERVER_EVENT_NAME, HostServerReady { port, url });
+        }
+    }
+
+    /// host_server 自身监听端口失败：ready 之前 uvicorn / asyncio 记录的端口占用错误。
+    /// MCP server 等子进程的输出也经过这里，它们自己的端口冲突不算
+    fn is_bind_failure(&self, record: &host_log::LogRecord) -> bool {
+        if self.ready.load(Ordering::SeqCst) || !record.is_error() {
+            return false;
+        }
+        if !["uvicorn", "asyncio"]
+            .iter()
+            .any(|logger| record.logger.starts_with(logger))
+        {
+            return false;
+        }
+        let message = record.message.to_lowercase();
+        HOST_SERVER_PORT_IN_USE_PATTERNS
+            .iter()
+            .any(|pattern| message.contains(pattern))
+    }
+
+    /// 处理一行输出：检测端口占用，并把 ERROR / CRITICAL 记录加入诊断尾部
+    fn observe(&self, line: &str, record: &host_log::LogRecord) {
+        if self.is_bind_failure(record) {
+            self.port_in_use.store(true, Ordering::SeqCst);
+        }
+        if !record.is_error() {
+            return;
+        }
+        let mut tail = self.stderr_tail.lock().unwrap();
+        if tail.len() >= HOST_SERVER_STDERR_TAIL_LINES {
+            tail.pop_front();
+        }
+        tail.push_back(line.to_string());
+    }
+
+    fn stderr_tail(&self) -> Vec<String> {
+        self.stderr_tail.lock().unwrap().iter().cloned().collect()
+    }
+}
+
+/// 每次（重新）启动 host_server 都使用同一份启动参数
+struct LaunchConfig {
+    binary_path: PathBuf,
+    mcp_config_path: PathBuf,
+    agent_config_path: PathBuf,
+    env: BTreeMap<String, String>,
+    /// 该版本的 host_server 是否从 stdin 读取密钥
+    stdin_secret: bool,
+}
+
+/// 当前平台的 host_server 二进制名，同时也是其所在目录名
+pub fn binary_name() -> &'static str {
+    #[cfg(target_os = "macos")]
+    let binary_name = "host_server_macos";
+
+    #[cfg(target_os = "linux")]
+    let binary_name = "host_server_linux";
+
+    #[cfg(target_os = "windows")]
+    let binary_name = "host_server_windows";
+
+    #[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "windows")))]
+    let binary_name = "host_server_macos";
+
+    binary_name
+}
+
+/// host_server 目录（名为 binary_name）中的可执行文件；Windows 上带 .exe
+///
+/// 启动前要校验文件本身，不能依赖 Command::new 自动补全扩展名。
+pub fn binary_file(dir: &Path) -> PathBuf {
+    if cfg!(target_os = "windows") {
+        dir.join(format!("{}.exe", binary_name()))
+    } else {
+        dir.join(binary_name())
+    }
+}
+
+/// 随应用打包的 host_server
+pub fn get_bundled_host_server_path<H: Host>(app: &H) -> PathBuf {
+    let binary_name = binary_name();
+    let dir = if cfg!(debug_assertions) {
+        std::env::current_dir()
+            .unwrap()
+            .join(format!("../node_modules/{}", binary_name))
+    } else {
+        app.resource_dir()
+            .unwrap()
+            .join(format!("resources/{}", binary_name))
+    };
+    binary_file(&dir)
+}
+
+/// 优先使用独立更新安装的版本，没有时回退到打包版本
+fn get_host_server_path<H: Host>(app: &H) -> PathBuf {
+    match host_update::active_binary(&app.app_config()) {
+        Some(path) => {
+            log::info!("Using updated host_server: {:?}", path);
+            path
+        }
+        None => get_bundled_host_server_path(app),
+    }
+}
+
+fn append_bin_to_path<H: Host>(app: &H) -> String {
+    let bin_dir = if cfg!(debug_assertions) {
+        std::env::current_dir().unwrap().join("bin")
+    } else {
+        app.resource_dir()
+            .unwrap()
+            .join("bin")
+    };
+    let bin_dir_str = bin_dir.to_string_lossy();
+    let shell_path = shell_env::shell_path(&app.app_config())
+        .unwrap_or_else(|| std::env::var("PATH").unwrap_or_default());
+    let sep: &'static str = if cfg!(target_os = "windows") {
+        ";"
+    } else {
+        ":"
+    };
+    format!("{}{}{}", bin_dir_str, sep, shell_path)
+}
+
+/// 外部 host_server 地址：命令行 --host-server-url 优先，其次环境变量 AIDEN_HOST_SERVER_URL
+pub fn external_url() -> Option<String> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let prefix = format!("{}=", HOST_SERVER_EXTERNAL_URL_FLAG);
+    let from_args = args.iter().enumerate().find_map(|(i, arg)| {
+        if arg == HOST_SERVER_EXTERNAL_URL_FLAG {
+            args.get(i + 1).cloned()
+        } else {
+            arg.strip_prefix(&prefix).map(String::from)
+        }
+    });
+    from_args
+        .or_else(|| std::env::var(HOST_SERVER_EXTERNAL_URL_ENV).ok())
+        .map(|url| url.trim().trim_end_matches('/').to_string())
+        .filter(|url| !url.is_empty())
+}
+
+/// 清单版本不低于 HOST_SERVER_STDIN_SECRET_VERSION 的 host_server 才从 stdin 读取密钥；
+/// 没有清单（开发模式）或版本无法解析时按旧版处理
+fn reads_stdin_secret(binary_path: &Path) -> bool {
+    let minimum = host_update::parse_version(HOST_SERVER_STDIN_SECRET_VERSION);
+    integrity::load_manifest(binary_path)
+        .ok()
+        .and_then(|manifest| host_update::parse_version(&manifest.version))
+        .map_or(false, |version| Some(version) >= minimum)
+}
+
+/// 外部 host_server 模式的密钥：开发者把同一行密钥写入 host_server 与 Aiden 的 stdin
+static EXTERNAL_SECRET: Mutex<Option<String>> = Mutex::new(None);
+static READ_EXTERNAL_SECRET: Once = Once::new();
+
+/// 在后台线程读取 Aiden stdin 的第一行作为外部 host_server 的密钥；stdin 是终端时会一直等待输入
+fn read_external_secret() {
+    READ_EXTERNAL_SECRET.call_once(|| {
+        std::thread::spawn(|| {
+            let mut line = String::new();
+            if std::io::stdin().read_line(&mut line).is_ok() {
+                let secret = line.trim();
+                if !secret.is_empty() {
+                    *EXTERNAL_SECRET.lock().unwrap() = Some(secret.to_string());
+                }
+            }
+        });
+    });
+}
+
+/// 生成随机密钥（十六进制）；每次启动 host_server 都换一个新的
+fn generate_secret() -> String {
+    use rand::RngCore;
+    let mut bytes = [0u8; HOST_SERVER_SECRET_BYTES];
+    rand::rngs::OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 由系统分配一个空闲端口，跳过本轮已经失败过的端口
+fn find_free_port(avoid: &[u16]) -> Option<u16> {
+    (0..=avoid.len()).find_map(|_| {
+        TcpListener::bind("127.0.0.1:0")
+            .ok()
+            .and_then(|listener| listener.local_addr().ok())
+            .map(|addr| addr.port())
+            .filter(|port| !avoid.contains(port))
+    })
+}
+
+/// 开发模式的固定端口，默认 6888，可用 AIDEN_HOST_SERVER_PORT 覆盖
+fn dev_port() -> u16 {
+    match std::env::var(HOST_SERVER_DEV_PORT_ENV) {
+        Ok(value) => value.trim().parse::<u16>().unwrap_or_else(|_| {
+            log::warn!(
+                "Invalid {}={:?}, using port {}",
+                HOST_SERVER_DEV_PORT_ENV,
+                value,
+                HOST_SERVER_DEV_PORT
+            );
+            HOST_SERVER_DEV_PORT
+        }),
+        Err(_) => HOST_SERVER_DEV_PORT,
+    }
+}
+
+fn choose_port(avoid: &[u16]) -> Option<u16> {
+    if cfg!(debug_assertions) {
+        let port = dev_port();
+        log::info!("Development mode, using fixed port {}", port);
+        Some(port)
+    } else {
+        let free_port = find_free_port(avoid)?;
+        log::info!("Production mode, using dynamic port: {}", free_port);
+        Some(free_port)
+    }
+}
+
+fn resolve_launch_config<H: Host>(app: &H) -> LaunchConfig {
+    let binary_path: PathBuf = get_host_server_path(app);
+    let config = app.app_config();
+    let mcp_config_path = mcp::get_user_config_path(&config).expect("Cannot get MCP config path");
+    let agent_config_path =
+        agent::get_user_config_path(&config).expect("Cannot get Agent config path");
+    log::info!("Starting host server from: {:?}", binary_path);
+    log::info!("Using config file from: {:?}", mcp_config_path);
+    log::info!("Using agent config file from: {:?}", agent_config_path);
+
+    let path_env = append_bin_to_path(app);
+    let env = host_env::build_host_server_env(app, &path_env);
+    let stdin_secret = reads_stdin_secret(&binary_path);
+    if !stdin_secret {
+        log::warn!(
+            "host_server older than {} (or without a manifest), the webview gets its token from /authorization/token",
+            HOST_SERVER_STDIN_SECRET_VERSION
+        );
+    }
+
+    LaunchConfig {
+        binary_path,
+        mcp_config_path,
+        agent_config_path,
+        env,
+        stdin_secret,
+    }
+}
+
+fn spawn_host_server(
+    launch: &LaunchConfig,
+    port: u16,
+    secret: Option<&str>,
+) -> std::io::Result<Child> {
+    let mut args = vec![
+        "--config_file".to_string(),
+        launch.mcp_config_path.to_string_lossy().to_string(),
+        "--agent_config_file".to_string(),
+        launch.agent_config_path.to_string_lossy().to_string(),
+        "--disable_reload".to_string(),
+        "--enable_authorization".to_string(),
+        "--port".to_string(),
+        port.to_string(),
+    ];
+
+    if cfg!(debug_assertions) {
+        args.push("--is_dev".into());
+    }
+
+    let mut cmd = StdCommand::new(&launch.binary_path);
+    // 密钥走 stdin，不出现在命令行（ps 可见）、日志和 MCP server 继承的环境变量中
+    cmd.args(&args)
+        .env_clear()
+        .envs(&launch.env)
+        .stdin(if secret.is_some() {
+            StdStdio::piped()
+        } else {
+            StdStdio::null()
+        })
+        .stdout(StdStdio::piped())
+        .stderr(StdStdio::piped());
+
+    #[cfg(target_os = "windows")]
+    cmd.creation_flags(CREATE_NO_WINDOW);
+
+    // 独立进程组，退出时只向这一组进程发送信号
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+        // Aiden 意外退出时由内核通知 host_server 退出
+        #[cfg(target_os = "linux")]
+        unsafe {
+            cmd.pre_exec(|| {
+                nix::sys::prctl::set_pdeathsig(nix::sys::signal::Signal::SIGTERM)?;
+                Ok(())
+            });
+        }
+    }
+
+    let mut child = TokioCommand::from(cmd).spawn()?;
+    if let Some(secret) = secret {
+        send_secret(&mut child, secret);
+    }
+    Ok(child)
+}
+
+/// 向 host_server 的 stdin 写入一行密钥后关闭；它启动的 stdio MCP server 使用各自的管道，读不到这一行
+fn send_secret(child: &mut Child, secret: &str) {
+    if let Some(mut stdin) = child.stdin.take() {
+        let line = format!("{}\n", secret);
+        task::spawn(async move {
+            if let Err(e) = stdin.write_all(line.as_bytes()).await {
+                log::warn!("Failed to pass the secret to host_server: {}", e);
+            }
+        });
+    }
+}
+
+/// 接管 stdout / stderr：写日志，并在 stdout 中检测 ready 文本（快速路径）
+fn attach_output<H: Host>(
+    app: &H,
+    child: &mut Child,
+    port: u16,
+    readiness: Arc<Readiness>,
+) -> task::JoinHandle<()> {
+    let stdout = child.stdout.take().expect("Failed to capture stdout");
+    let stderr = child.stderr.take().expect("Failed to capture stderr");
+
+    // stdout 日志 + ready 检测
+    let app_clone = app.clone();
+    let stdout_readiness = readiness.clone();
+    task::spawn(async move {
+        let reader = tokio::io::BufReader::new(stdout);
+        let mut lines = reader.lines();
+        let mut parser = host_log::LogParser::default();
+        let expected_ready_text = format!("{}{}", HOST_SERVER_READY_TEXT, port);
+        while let Ok(Some(line)) = lines.next_line().await {
+            let record = parser.parse(&line, host_log::Stream::Stdout);
+            host_log::emit(&record);
+            host_log::record(&app_clone, host_log::Stream::Stdout, &record);
+            stdout_readiness.observe(&line, &record);
+            if line.contains(&expected_ready_text) {
+                stdout_readiness.mark_ready(&app_clone, port);
+            }
+        }
+    });
+
+    // stderr 日志：Python logging 默认写 stderr，按解析出的级别记录，只有 ERROR 以上计入诊断
+    let app_clone = app.clone();
+    task::spawn(async move {
+        let reader = tokio::io::BufReader::new(stderr);
+        let mut lines = reader.lines();
+        let mut parser = host_log::LogParser::default();
+        while let Ok(Some(line)) = lines.next_line().await {
+            let record = parser.parse(&line, host_log::Stream::Stderr);
+            host_log::emit(&record);
+            host_log::record(&app_clone, host_log::Stream::Stderr, &record);
+            readiness.observe(&line, &record);
+            // if record.is_error() {
+            //     sentry::capture_message(
+            //         &format!("host_server: {}", record.message),
+            //         sentry::Level::Error,
+            //     );
+            // }
+        }
+    })
+}
+
+/// 轮询健康检查；超时后发出 host_server_failed，附带最近的 stderr
+async fn watch_readiness<H: Host>(app: H, port: u16, readiness: Arc<Readiness>) {
+    let timeout = health::ready_timeout();
+    let ready = || readiness.ready.load(Ordering::SeqCst);
+    if health::wait_until_ready(&health::local_url(port), timeout, ready).await {
+        readiness.mark_ready(&app, port);
+        return;
+    }
+    if readiness.ready.load(Ordering::SeqCst) {
+        return;
+    }
+    log::error!(
+        "host_server did not become ready on port {} within {:?}",
+        port,
+        timeout
+    );
+    emit(
+        &app,
+        HOST_SERVER_FAILED_EVENT,
+        HostServerFailed::ReadyTimeout {
+            port,
+            timeout_secs: timeout.as_secs(),
+            stderr_tail: readiness.stderr_tail(),
+        },
+    );
+}
+
+fn restart_delay(attempt: u32) -> Duration {
+    let factor = 1u64 << attempt.saturating_sub(1).min(16);
+    Duration::from_millis(
+        HOST_SERVER_RESTART_BACKOFF_MS
+            .saturating_mul(factor)
+            .min(HOST_SERVER_RESTART_BACKOFF_MAX_MS),
+    )
+}
+
+fn emit<H: Host, S: Serialize + Clone>(app: &H, event: &str, payload: S) {
+    if let Err(e) = app.emit_event(event, payload) {
+        log::error!("Failed to emit {} to frontend: {}", event, e);
+    }
+}
+
+/// 启动 host_server，并在后台监督其退出状态，崩溃后按指数退避自动重启
+pub fn launch_host_server<H: Host>(
+    app: &H,
+    state: &HostServerProcess,
+) -> Result<(), String> {
+    let external = external_url();
+    let (tx, rx) = mpsc::unbounded_channel();
+    // 检查与登记 supervisor 在同一把锁内完成，并发调用时只有一个能启动
+    {
+        let mut guard = state.0.lock().unwrap();
+        if guard.supervisor.is_some() {
+            return Err("host_server is already running".into());
+        }
+        // 上一个 supervisor 已被要求停止，但旧进程还没退出
+        if let Some(pid) = guard.pid {
+            return Err(format!("host_server (pid {}) is still stopping", pid));
+        }
+        guard.supervisor = Some(tx);
+    }
+    match external {
+        Some(url) => task::spawn(watch_external(app.clone(), url, rx)),
+        None => task::spawn(supervise(app.clone(), rx)),
+    };
+    Ok(())
+}
+
+/// 启动参数只解析一次，重启沿用同一份配置路径和 PATH
+///
+/// 首次解析要运行登录 shell（最长 SHELL_PATH_TIMEOUT），放到阻塞线程，不占用 setup。
+async fn launch_config<H: Host>(app: &H) -> Result<Arc<LaunchConfig>, String> {
+    let resolving = app.host_server().0.lock().unwrap().resolving.clone();
+    let _resolving = resolving.lock().await;
+    if let Some(launch) = app.host_server().0.lock().unwrap().launch.clone() {
+        return Ok(launch);
+    }
+    let resolver = app.clone();
+    let launch = task::spawn_blocking(move || resolve_launch_config(&resolver))
+        .await
+        .map(Arc::new)
+        .map_err(|e| format!("Failed to resolve host_server launch config: {}", e))?;
+    app.host_server().0.lock().unwrap().launch = Some(launch.clone());
+    Ok(launch)
+}
+
+async fn supervise<H: Host>(app: H, mut commands: mpsc::UnboundedReceiver<SupervisorCommand>) {
+    let launch = match launch_config(&app).await {
+        Ok(launch) => launch,
+        Err(e) => {
+            log::error!("{}", e);
+            app.host_server().0.lock().unwrap().supervisor = None;
+            finish(&app, None, None);
+            return;
+        }
+    };
+    // 每次启动 supervisor 时校验一次，崩溃重启沿用同一个二进制
+    if let Err(e) = integrity::verify_host_server(&launch.binary_path, &launch.env).await {
+        log::error!("Refusing to start host_server: {}", e);
+        emit(
+            &app,
+            HOST_SERVER_FAILED_EVENT,
+            HostServerFailed::VerificationFailed(e),
+        );
+        app.host_server().0.lock().unwrap().supervisor = None;
+        finish(&app, None, None);
+        return;
+    }
+
+    // 连续崩溃次数，运行超过 HOST_SERVER_STABLE_UPTIME_SECS 后清零
+    let mut attempt: u32 = 0;
+    // 因端口被抢占而失败的端口，换端口重试时跳过；启动成功后清空
+    let mut ports_in_use: Vec<u16> = Vec::new();
+
+    loop {
+        let started_at = Instant::now();
+        let readiness = Arc::new(Readiness::default());
+        let mut pid = None;
+        let mut port = 0;
+        let secret = launch.stdin_secret.then(generate_secret);
+
+        let exit_code = match choose_port(&ports_in_use)
+            .ok_or_else(|| "Failed to find a free port".to_string())
+            .and_then(|p| {
+                port = p;
+                spawn_host_server(&launch, p, secret.as_deref()).map_err(|e| e.to_string())
+            }) {
+            Ok(mut child) => {
+                pid = child.id();
+                log::info!("host_server started, pid={:?}, port={}", pid, port);
+                let stderr_reader = attach_output(&app, &mut child, port, readiness.clone());
+                let readiness_watch =
+                    task::spawn(watch_readiness(app.clone(), port, readiness.clone()));
+                {
+                    let state = app.host_server();
+                    let mut guard = state.0.lock().unwrap();
+                    guard.pid = pid;
+                    guard.port = Some(port);
+                    guard.started_at = Some(started_at);
+                    guard.ready = false;
+                    guard.secret = secret.clone();
+                }
+
+                let mut tree = ProcessTree::record(app.app_config(), pid);
+                let mut refresh = tokio::time::interval(HOST_SERVER_TREE_REFRESH);
+
+                let exit_code = loop {
+                    tokio::select! {
+                        status = child.wait() => break match status {
+                            Ok(status) => {
+                                log::warn!("host_server exited: {}", process::describe_exit(&status));
+                                status.code()
+                            }
+                            Err(e) => {
+                                log::error!("Failed to wait for host_server: {}", e);
+                                None
+                            }
+                        },
+                        command = commands.recv() => {
+                            readiness_watch.abort();
+                            // 结束前取一次后代进程，host_server 退出后它们会被收养，无法再按父子关系找到
+                            tree.refresh(false);
+                            let status = process::terminate_gracefully(
+                                "host_server",
+                                &mut child,
+                                HOST_SERVER_SHUTDOWN_GRACE,
+                            )
+                            .await;
+                            tree.reap().await;
+                            finish(&app, command, status.and_then(|s| s.code()));
+                            return;
+                        }
+                        _ = refresh.tick() => tree.refresh(true),
+                    }
+                };
+                // 进程已退出，不再需要等待 ready；回收它遗留的 MCP server
+                readiness_watch.abort();
+                // 读完最后的 stderr 再判断退出原因；后代进程可能仍持有管道，所以限时等待
+                let _ = tokio::time::timeout(Duration::from_secs(1), stderr_reader).await;
+                tree.reap().await;
+                exit_code
+            }
+            Err(e) => {
+                log::error!("Failed to start host_server: {}", e);
+                emit(
+                    &app,
+                    HOST_SERVER_FAILED_EVENT,
+                    HostServerFailed::SpawnFailed { message: e },
+                );
+                None
+            }
+        };
+
+        let uptime = started_at.elapsed();
+        {
+            let state = app.host_server();
+            let mut guard = state.0.lock().unwrap();
+            guard.clear_process();
+            guard.last_exit_code = exit_code;
+        }
+
+        // 端口在分配后被其他进程抢占：换一个端口立即重试，不计入崩溃次数
+        let ready = readiness.ready.load(Ordering::SeqCst);
+        if ready {
+            ports_in_use.clear();
+        } else if readiness.port_in_use.load(Ordering::SeqCst) {
+            ports_in_use.push(port);
+            let retries = ports_in_use.len() as u32;
+            if cfg!(debug_assertions) || retries > HOST_SERVER_PORT_RETRIES {
+                log::error!(
+                    "host_server could not bind port {} (retried {} time(s)){}",
+                    port,
+                    retries - 1,
+                    if cfg!(debug_assertions) {
+                        format!(", set {} to use another port", HOST_SERVER_DEV_PORT_ENV)
+                    } else {
+                        String::new()
+                    }
+                );
+                emit(
+                    &app,
+                    HOST_SERVER_FAILED_EVENT,
+                    HostServerFailed::PortInUse {
+                        port,
+                        retries: retries - 1,
+                        stderr_tail: readiness.stderr_tail(),
+                    },
+                );
+                app.host_server().0.lock().unwrap().supervisor = None;
+                return;
+            }
+            log::warn!(
+                "Port {} was taken before host_server could bind it, retrying with a new port ({}/{})",
+                port,
+                retries,
+                HOST_SERVER_PORT_RETRIES
+            );
+            match commands.try_recv() {
+                Err(mpsc::error::TryRecvError::Empty) => continue,
+                Ok(command) => {
+                    finish(&app, Some(command), exit_code);
+                    return;
+                }
+                Err(mpsc::error::TryRecvError::Disconnected) => {
+                    finish(&app, None, exit_code);
+                    return;
+                }
+            }
+        }
+        emit(
+            &app,
+            HOST_SERVER_EXITED_EVENT,
+            HostServerExited {
+                pid,
+                port,
+                code: exit_code,
+                uptime_secs: uptime.as_secs(),
+            },
+        );
+
+        if uptime >= Duration::from_secs(HOST_SERVER_STABLE_UPTIME_SECS) {
+            attempt = 0;
+        }
+        attempt += 1;
+        if attempt > HOST_SERVER_MAX_RESTARTS {
+            log::error!(
+                "host_server crashed {} times in a row, giving up",
+                attempt - 1
+            );
+            emit(
+                &app,
+                HOST_SERVER_FAILED_EVENT,
+                HostServerFailed::CrashLoop {
+                    restarts: attempt - 1,
+                    stderr_tail: readiness.stderr_tail(),
+                },
+            );
+            app.host_server().0.lock().unwrap().supervisor = None;
+            return;
+        }
+
+        let delay = restart_delay(attempt);
+        log::info!(
+            "Restarting host_server in {:?} (attempt {}/{})",
+            delay,
+            attempt,
+            HOST_SERVER_MAX_RESTARTS
+        );
+        app.host_server()
+            .0
+            .lock()
+            .unwrap()
+            .restart_count += 1;
+        emit(
+            &app,
+            HOST_SERVER_RESTARTING_EVENT,
+            HostServerRestarting {
+                attempt,
+                delay_ms: delay.as_millis() as u64,
+            },
+        );
+
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            command = commands.recv() => {
+                finish(&app, command, exit_code);
+                return;
+            }
+        }
+    }
+}
+
+/// 外部 host_server 模式：不启动、不结束任何进程，只做健康检查并上报 ready
+async fn watch_external<H: Host>(
+    app: H,
+    url: String,
+    mut commands: mpsc::UnboundedReceiver<SupervisorCommand>,
+) {
+    log::info!("Using external host_server at {}, not spawning the bundled one", url);
+    read_external_secret();
+    let port = reqwest::Url::parse(&url)
+        .ok()
+        .and_then(|parsed| parsed.port_or_known_default());
+    app.host_server()
+        .0
+        .lock()
+        .unwrap()
+        .external_url = Some(url.clone());
+
+    let timeout = health::ready_timeout();
+    tokio::select! {
+        ready = health::wait_until_ready(&url, timeout, || false) => {
+            match (ready, port) {
+                (true, Some(port)) => {
+                    log::info!("External host_server at {} is ready", url);
+                    {
+                        let state = app.host_server();
+                        let mut guard = state.0.lock().unwrap();
+                        guard.port = Some(port);
+                        guard.started_at = Some(Instant::now());
+                        guard.ready = true;
+                        // 与内置模式同一通道：密钥来自 stdin，没有时前端改用 /authorization/token
+                        guard.secret = EXTERNAL_SECRET.lock().unwrap().clone();
+                        if guard.secret.is_none() {
+                            log::warn!(
+                                "No secret on stdin for the external host_server, using /authorization/token"
+                            );
+                        }
+                    }
+                    emit(
+                        &app,
+                        HOST_SERVER_EVENT_NAME,
+                        HostServerReady {
+                            port,
+                            url: url.clone(),
+                        },
+                    );
+                }
+                (ready, _) => {
+                    log::error!(
+                        "External host_server at {} is not usable (ready: {}, port: {:?})",
+                        url,
+                        ready,
+                        port
+                    );
+                    emit(
+                        &app,
+                        HOST_SERVER_FAILED_EVENT,
+                        HostServerFailed::ReadyTimeout {
+                            port: port.unwrap_or(0),
+                            timeout_secs: timeout.as_secs(),
+                            stderr_tail: Vec::new(),
+                        },
+                    );
+                    app.host_server().0.lock().unwrap().supervisor = None;
+                    finish(&app, None, None);
+                    return;
+                }
+            }
+        }
+        command = commands.recv() => {
+            finish(&app, command, None);
+            return;
+        }
+    }
+
+    // 进程由开发者自己管理，停止时只是不再使用它
+    let command = commands.recv().await;
+    finish(&app, command, None);
+}
+
+/// host_server 的进程树记录：退出时回收它的后代进程，并写入 run_state 供下次启动时清理崩溃遗留
+///
+/// 进程树读自 /proc，只在 Linux 上记录；其他平台停止时只按进程组结束 host_server。
+#[cfg(target_os = "linux")]
+struct ProcessTree {
+    config: Arc<tauri::Config>,
+    pid: Option<u32>,
+    host_server: Option<ProcessInfo>,
+    descendants: Vec<ProcessInfo>,
+}
+
+#[cfg(target_os = "linux")]
+impl ProcessTree {
+    fn record(config: Arc<tauri::Config>, pid: Option<u32>) -> Self {
+        let host_server = pid.and_then(process::process_info);
+        run_state::record_host_server(&config, host_server.clone(), Vec::new());
+        ProcessTree {
+            config,
+            pid,
+            host_server,
+            descendants: Vec::new(),
+        }
+    }
+
+    /// 重新读取后代进程；save 时有变化就写入 run_state
+    fn refresh(&mut self, save: bool) {
+        let pid = match self.pid {
+            Some(pid) => pid,
+            None => return,
+        };
+        let current = process::descendants(pid);
+        if current != self.descendants {
+            self.descendants = current;
+            if save {
+                run_state::record_host_server(
+                    &self.config,
+                    self.host_server.clone(),
+                    self.descendants.clone(),
+                );
+            }
+        }
+    }
+
+    /// 结束遗留的后代进程（已脱离进程组或 host_server 崩溃后被收养的），并清空记录
+    async fn reap(self) {
+        let ProcessTree {
+            config,
+            descendants,
+            ..
+        } = self;
+        if !descendants.is_empty() {
+            let _ = task::spawn_blocking(move || {
+                process::kill_processes(
+                    "host_server descendant",
+                    &descendants,
+                    HOST_SERVER_SHUTDOWN_GRACE,
+                )
+            })
+            .await;
+        }
+        run_state::clear_host_server(&config);
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+struct ProcessTree;
+
+#[cfg(not(target_os = "linux"))]
+impl ProcessTree {
+    fn record(_config: Arc<tauri::Config>, _pid: Option<u32>) -> Self {
+        ProcessTree
+    }
+
+    fn refresh(&mut self, _save: bool) {}
+
+    async fn reap(self) {}
+}
+
+/// supervisor 退出前清理状态并回应 Stop 请求
+fn finish<H: Host>(app: &H, command: Option<SupervisorCommand>, code: Option<i32>) {
+    {
+        let state = app.host_server();
+        let mut guard = state.0.lock().unwrap();
+        guard.clear_process();
+        guard.last_exit_code = code;
+    }
+    log::info!("host_server supervisor stopped");
+    if let Some(SupervisorCommand::Stop(done)) = command {
+        let _ = done.send(());
+    }
+}
+
+/// 通知 supervisor 停止 host_server（不再重启），返回用于等待完成的 receiver
+///
+/// 返回 None 表示没有正在运行的 supervisor。
+fn request_stop(state: &HostServerProcess) -> Option<std::sync::mpsc::Receiver<()>> {
+    // 先取出 sender 再释放锁，supervisor 退出时还需要拿锁
+    let supervisor = state.0.lock().unwrap().supervisor.take()?;
+    let (done_tx, done_rx) = std::sync::mpsc::channel();
+    supervisor.send(SupervisorCommand::Stop(done_tx)).ok()?;
+    Some(done_rx)
+}
+
+/// 同步停止 host_server，最多等待 timeout；用于退出流程
+pub fn stop_supervisor(state: &HostServerProcess, timeout: Duration) -> bool {
+    let done = match request_stop(state) {
+        Some(done) => done,
+        None => return false,
+    };
+    if done.recv_timeout(timeout).is_err() {
+        log::warn!("Timed out waiting for host_server supervisor to stop");
+    }
+    true
+}
+
+/// 异步版本的 stop_supervisor，供 Tauri 命令使用
+///
+/// Ok(false) 表示没有正在运行的 supervisor；旧进程在 timeout 内没有退出时返回错误。
+async fn stop_supervisor_async(
+    state: &HostServerProcess,
+    timeout: Duration,
+) -> Result<bool, String> {
+    let done = match request_stop(state) {
+        Some(done) => done,
+        None => return Ok(false),
+    };
+    let stopped = task::spawn_blocking(move || done.recv_timeout(timeout).is_ok())
+        .await
+        .unwrap_or(false);
+    if !stopped {
+        log::warn!("Timed out waiting for host_server supervisor to stop");
+        return Err(format!("host_server did not stop within {}s", timeout.as_secs()));
+    }
+    Ok(true)
+}
+
+/// 等待当前这次启动通过 ready 检测
+async fn wait_for_ready(state: &HostServerProcess, timeout: Duration) -> Result<(), String> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        {
+            let guard = state.0.lock().unwrap();
+            if guard.ready {
+                return Ok(());
+            }
+            if guard.supervisor.is_none() {
+                return Err("host_server stopped before it became ready".into());
+            }
+        }
+        if Instant::now() >= deadline {
+            return Err(format!(
+                "host_server did not become ready within {}s",
+                timeout.as_secs()
+            ));
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+}
+
+/// get_host_server_secret 的返回值：密钥，或由前端向旧版 host_server 的 /authorization/token 获取
+#[derive(Clone, Serialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum HostServerAuth {
+    Secret { secret: String },
+    TokenEndpoint,
+}
+
+/// 当前 host_server 的授权密钥，只对主窗口开放；host_server 重启后密钥会变化
+#[tauri::command]
+pub fn get_host_server_secret(
+    window: Window,
+    state: State<'_, HostServerProcess>,
+) -> Result<HostServerAuth, String> {
+    if window.label() != "main" {
+        log::warn!("Window {} requested the host_server secret, refused", window.label());
+        return Err("Not allowed".into());
+    }
+    let guard = state.0.lock().unwrap();
+    if !guard.ready {
+        return Err("host_server is not running".into());
+    }
+    Ok(match guard.secret.clone() {
+        Some(secret) => HostServerAuth::Secret { secret },
+        None => HostServerAuth::TokenEndpoint,
+    })
+}
+
+#[tauri::command]
+pub fn host_server_status(state: State<'_, HostServerProcess>) -> HostServerStatus {
+    HostServerStatus::from(&*state.0.lock().unwrap())
+}
+
+#[tauri::command]
+pub async fn stop_host_server(
+    state: State<'_, HostServerProcess>,
+) -> Result<HostServerStatus, String> {
+    if !stop_supervisor_async(&state, HOST_SERVER_STOP_TIMEOUT).await? {
+        return Err("host_server is not running".into());
+    }
+    Ok(HostServerStatus::from(&*state.0.lock().unwrap()))
+}
+
+#[tauri::command]
+pub async fn start_host_server(
+    app: AppHandle,
+    state: State<'_, HostServerProcess>,
+) -> Result<HostServerStatus, String> {
+    launch_host_server(&app, &state)?;
+    wait_for_ready(&state, health::ready_timeout()).await?;
+    Ok(HostServerStatus::from(&*state.0.lock().unwrap()))
+}
+
+/// 重启 host_server：等待旧进程退出、新进程 ready 后才返回
+#[tauri::command]
+pub async fn restart_host_server(
+    app: AppHandle,
+    state: State<'_, HostServerProcess>,
+) -> Result<HostServerStatus, String> {
+    log::info!("Restarting host_server on request");
+    relaunch_host_server(&app, &state, health::ready_timeout()).await
+}
+
+/// 停止当前 host_server，重新解析启动参数后启动，等待新进程 ready
+pub async fn relaunch_host_server<H: Host>(
+    app: &H,
+    state: &HostServerProcess,
+    timeout: Duration,
+) -> Result<HostServerStatus, String> {
+    // 旧进程没退出时不能再启动一个，否则会有两个 host_server
+    if stop_supervisor_async(state, HOST_SERVER_STOP_TIMEOUT).await? {
+        state.0.lock().unwrap().restart_count += 1;
+    }
+    // 重新解析启动参数，使修改过的环境变量覆盖、新安装的版本生效
+    state.0.lock().unwrap().launch = None;
+    launch_host_server(app, state)?;
+    wait_for_ready(state, timeout).await?;
+    Ok(HostServerStatus::from(&*state.0.lock().unwrap()))
+}
@@ -0,0 +1,736 @@
+use crate::constants::{
+    HOST_SERVER_FEED_URL_ENV, HOST_SERVER_UPDATED_EVENT, HOST_SERVER_UPDATE_PUBKEY,
+    HOST_SERVER_UPDATE_READY_TIMEOUT, HOST_SERVER_UPDATE_RETAIN,
+};
+use crate::host::Host;
+use crate::host_server::{self, HostServerProcess};
+use crate::integrity;
+use base64::Engine;
+use minisign_verify::{PublicKey, Signature};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::future::Future;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tauri::api::path::app_data_dir;
+use tauri::{AppHandle, Config};
+
+/// 发布源：每个平台一个 zip，zip 内为 host_server_<os>/ 目录（含二进制与校验清单）
+///
+/// eg: {"version": "v0.2.42", "platforms": {"host_server_macos": {"url": "...", "sha256": "...", "signature": "..."}}}
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReleaseFeed {
+    pub version: String,
+    pub platforms: BTreeMap<String, ReleaseAsset>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReleaseAsset {
+    pub url: String,
+    /// zip 文件本身的 sha256
+    pub sha256: String,
+    /// zip 的 minisign 签名，格式与应用更新 latest.json 中的 signature 相同（.sig 文件内容的 base64）
+    pub signature: String,
+}
+
+/// 当前生效版本的指针，整体替换写入，保证要么是旧值要么是新值
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ActivePointer {
+    pub version: Option<String>,
+    /// 切换前的版本，None 表示打包版本；用于回滚
+    pub previous: Option<String>,
+}
+
+/// get_host_server_version 命令的返回值
+#[derive(Debug, Clone, Serialize)]
+pub struct HostServerVersion {
+    pub version: Option<String>,
+    /// 是否在使用独立更新安装的版本（否则为打包版本）
+    pub updated: bool,
+    pub previous: Option<String>,
+}
+
+/// 更新检查的结果
+#[derive(Debug, Clone, Serialize)]
+pub struct HostServerUpdate {
+    /// 更新前使用的版本，None 表示打包版本
+    pub from: Option<String>,
+    pub to: String,
+    pub rolled_back: bool,
+    pub error: Option<String>,
+}
+
+/// eg: ~/Library/Application Support/com.aiden.chat/host_server/
+fn get_update_dir(config: &Config) -> Option<PathBuf> {
+    let mut path = app_data_dir(config)?;
+    path.push("host_server");
+    fs::create_dir_all(path.join("versions")).ok()?;
+    Some(path)
+}
+
+fn version_dir(update_dir: &Path, version: &str) -> PathBuf {
+    update_dir.join("versions").join(version)
+}
+
+/// 解压后的二进制：zip 内可能带一层 host_server_* 目录
+fn binary_in(dir: &Path) -> PathBuf {
+    let nested = host_server::binary_file(&dir.join(host_server::binary_name()));
+    if nested.exists() {
+        nested
+    } else {
+        host_server::binary_file(dir)
+    }
+}
+
+fn read_active(update_dir: &Path) -> ActivePointer {
+    fs::read_to_string(update_dir.join("active.json"))
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+pub fn load_active(config: &Config) -> ActivePointer {
+    get_update_dir(config)
+        .map(|dir| read_active(&dir))
+        .unwrap_or_default()
+}
+
+/// 写临时文件后 rename，rename 在同一文件系统内是原子的
+fn save_active(dir: &Path, pointer: &ActivePointer) -> Result<(), String> {
+    let tmp = dir.join("active.json.tmp");
+    let json = serde_json::to_string_pretty(pointer).map_err(|e| e.to_string())?;
+    let mut file = File::create(&tmp).map_err(|e| e.to_string())?;
+    file.write_all(json.as_bytes()).map_err(|e| e.to_string())?;
+    file.sync_all().map_err(|e| e.to_string())?;
+    fs::rename(&tmp, dir.join("active.json")).map_err(|e| e.to_string())
+}
+
+/// 当前生效的已更新版本二进制；没有或文件缺失时返回 None（使用打包版本）
+pub fn active_binary(config: &Config) -> Option<PathBuf> {
+    let version = load_active(config).version?;
+    let binary = binary_in(&version_dir(&get_update_dir(config)?, &version));
+    if binary.is_file() {
+        Some(binary)
+    } else {
+        log::warn!("Active host_server {} is missing at {:?}", version, binary);
+        None
+    }
+}
+
+pub fn parse_version(version: &str) -> Option<Version> {
+    Version::parse(version.trim().trim_start_matches('v')).ok()
+}
+
+/// 正在使用的版本：已更新版本，或打包版本清单中的版本
+fn current_version<H: Host>(app: &H) -> Option<String> {
+    load_active(&app.app_config()).version.or_else(|| {
+        integrity::load_manifest(&host_server::get_bundled_host_server_path(app))
+            .ok()
+            .map(|manifest| manifest.version)
+    })
+}
+
+/// 发布源地址：构建时注入的地址；debug 构建中运行时环境变量优先（可指向本地 HTTP 服务测试），
+/// release 构建忽略该环境变量。都没有时不启用独立更新
+pub fn feed_url() -> Option<String> {
+    let override_url = if cfg!(debug_assertions) {
+        std::env::var(HOST_SERVER_FEED_URL_ENV).ok()
+    } else {
+        None
+    };
+    override_url
+        .or_else(|| option_env!("AIDEN_HOST_SERVER_FEED_URL").map(String::from))
+        .filter(|url| !url.trim().is_empty())
+}
+
+fn is_loopback(url: &reqwest::Url) -> bool {
+    matches!(url.host_str(), Some("localhost" | "127.0.0.1" | "[::1]"))
+}
+
+/// 发布源与更新包只允许 https；debug 构建额外允许本机 http，方便本地测试
+fn parse_url(url: &str) -> Result<reqwest::Url, String> {
+    let parsed = reqwest::Url::parse(url).map_err(|e| format!("Invalid URL {}: {}", url, e))?;
+    match parsed.scheme() {
+        "https" => Ok(parsed),
+        "http" if cfg!(debug_assertions) && is_loopback(&parsed) => Ok(parsed),
+        _ => Err(format!("Refusing to download from non-https URL {}", url)),
+    }
+}
+
+fn http_client(url: &str) -> Result<reqwest::Client, String> {
+    let builder = reqwest::Client::builder();
+    let builder = if is_loopback(&parse_url(url)?) {
+        builder.no_proxy()
+    } else {
+        builder
+    };
+    builder.build().map_err(|e| e.to_string())
+}
+
+/// 公钥与签名都是 base64 编码的 minisign 文本，与 Tauri updater 相同
+fn verify_signature(public_key: &str, data: &[u8], signature: &str) -> Result<(), String> {
+    let decode = |text: &str| {
+        base64::engine::general_purpose::STANDARD
+            .decode(text.trim())
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| String::from_utf8(bytes).map_err(|e| e.to_string()))
+    };
+    let public_key = decode(public_key)
+        .and_then(|text| PublicKey::decode(&text).map_err(|e| e.to_string()))
+        .map_err(|e| format!("Invalid update public key: {}", e))?;
+    let signature = decode(signature)
+        .and_then(|text| Signature::decode(&text).map_err(|e| e.to_string()))
+        .map_err(|e| format!("Invalid update signature: {}", e))?;
+    public_key
+        .verify(data, &signature, true)
+        .map_err(|e| format!("Update signature verification failed: {}", e))
+}
+
+async fn fetch_feed(url: &str) -> Result<ReleaseFeed, String> {
+    http_client(url)?
+        .get(url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("Failed to fetch release feed {}: {}", url, e))?
+        .text()
+        .await
+        .map_err(|e| format!("Failed to fetch release feed {}: {}", url, e))
+        .and_then(|text| {
+            serde_json::from_str(&text).map_err(|e| format!("Invalid release feed {}: {}", url, e))
+        })
+}
+
+async fn download(url: &str, dest: &Path) -> Result<(), String> {
+    let bytes = http_client(url)?
+        .get(url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("Failed to download {}: {}", url, e))?
+        .bytes()
+        .await
+        .map_err(|e| format!("Failed to download {}: {}", url, e))?;
+    fs::write(dest, &bytes).map_err(|e| e.to_string())
+}
+
+/// 解压 zip；拒绝解压到目标目录之外的条目
+fn extract_zip(zip_path: &Path, dest: &Path) -> Result<(), String> {
+    let file = File::open(zip_path).map_err(|e| e.to_string())?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| e.to_string())?;
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i).map_err(|e| e.to_string())?;
+        let relative = entry
+            .enclosed_name()
+            .ok_or_else(|| format!("Unsafe path in zip: {}", entry.name()))?;
+        let out_path = dest.join(relative);
+        if entry.is_dir() {
+            fs::create_dir_all(&out_path).map_err(|e| e.to_string())?;
+            continue;
+        }
+        if let Some(parent) = out_path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let mut out = File::create(&out_path).map_err(|e| e.to_string())?;
+        std::io::copy(&mut entry, &mut out).map_err(|e| e.to_string())?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            if let Some(mode) = entry.unix_mode() {
+                fs::set_permissions(&out_path, fs::Permissions::from_mode(mode & 0o777))
+                    .map_err(|e| e.to_string())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 下载、校验签名与 sha256 并解压到 versions/<version>；先解压到临时目录，完整后再 rename
+async fn install(
+    update_dir: &Path,
+    version: &str,
+    asset: &ReleaseAsset,
+    public_key: &str,
+) -> Result<(), String> {
+    let target = version_dir(update_dir, version);
+    if binary_in(&target).is_file() {
+        log::info!("host_server {} is already installed", version);
+        return Ok(());
+    }
+    let zip_path = update_dir.join(format!("{}.zip", version));
+    let staging = update_dir.join(format!("{}.staging", version));
+    let _ = fs::remove_dir_all(&staging);
+
+    log::info!("Downloading host_server {} from {}", version, asset.url);
+    download(&asset.url, &zip_path).await?;
+    let result = (|| {
+        let actual = integrity::sha256_file(&zip_path).map_err(|e| e.to_string())?;
+        if !actual.eq_ignore_ascii_case(asset.sha256.trim()) {
+            return Err(format!(
+                "sha256 mismatch for host_server {}: expected {}, got {}",
+                version, asset.sha256, actual
+            ));
+        }
+        let bytes = fs::read(&zip_path).map_err(|e| e.to_string())?;
+        verify_signature(public_key, &bytes, &asset.signature)
+            .map_err(|e| format!("host_server {}: {}", version, e))?;
+        extract_zip(&zip_path, &staging)?;
+        let binary = binary_in(&staging);
+        if !binary.is_file() {
+            return Err(format!("{:?} not found in host_server {} zip", binary, version));
+        }
+        // 清单随版本目录一起发布，启动前由 integrity 再校验一次
+        let manifest = integrity::load_manifest(&binary).map_err(|e| e.to_string())?;
+        if parse_version(&manifest.version) != parse_version(version) {
+            return Err(format!(
+                "Manifest version {} does not match release {}",
+                manifest.version, version
+            ));
+        }
+        let _ = fs::remove_dir_all(&target);
+        fs::rename(&staging, &target).map_err(|e| e.to_string())
+    })();
+    let _ = fs::remove_file(&zip_path);
+    if result.is_err() {
+        let _ = fs::remove_dir_all(&staging);
+    }
+    result
+}
+
+/// 只保留当前、上一个以及最近的若干个版本目录
+fn prune_versions(update_dir: &Path, pointer: &ActivePointer) {
+    let entries = match fs::read_dir(update_dir.join("versions")) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    let mut versions: Vec<(Version, PathBuf)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            Some((parse_version(&name)?, entry.path()))
+        })
+        .collect();
+    versions.sort_by(|a, b| b.0.cmp(&a.0));
+    let keep: Vec<Option<Version>> = vec![
+        pointer.version.as_deref().and_then(parse_version),
+        pointer.previous.as_deref().and_then(parse_version),
+    ];
+    for (index, (version, path)) in versions.into_iter().enumerate() {
+        if index < HOST_SERVER_UPDATE_RETAIN || keep.contains(&Some(version)) {
+            continue;
+        }
+        log::info!("Removing old host_server version {:?}", path);
+        let _ = fs::remove_dir_all(path);
+    }
+}
+
+/// 切换到已安装的 version 并通过 relaunch 重启；新版本未能按时 ready 时切回之前的版本再重启一次
+async fn activate<F, Fut>(
+    update_dir: &Path,
+    from: Option<String>,
+    version: &str,
+    relaunch: F,
+) -> Result<HostServerUpdate, String>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<(), String>>,
+{
+    let previous = read_active(update_dir);
+    let switched = ActivePointer {
+        version: Some(version.to_string()),
+        previous: previous.version.clone(),
+    };
+    save_active(update_dir, &switched)?;
+    log::info!("Switched host_server to {} (was {:?})", version, from);
+
+    let mut update = HostServerUpdate {
+        from,
+        to: version.to_string(),
+        rolled_back: false,
+        error: None,
+    };
+    if let Err(e) = relaunch().await {
+        log::error!(
+            "host_server {} did not become ready ({}), rolling back",
+            version,
+            e
+        );
+        save_active(
+            update_dir,
+            &ActivePointer {
+                version: previous.version.clone(),
+                previous: None,
+            },
+        )?;
+        update.rolled_back = true;
+        update.error = Some(e);
+        if let Err(e) = relaunch().await {
+            log::error!("host_server failed to start after rollback: {}", e);
+        }
+        // 失败的版本不再保留，下次检查时会重新下载
+        let _ = fs::remove_dir_all(version_dir(update_dir, version));
+    } else {
+        prune_versions(update_dir, &switched);
+    }
+    Ok(update)
+}
+
+/// 持有期间 state.updating 为 true；结束时（包括出错与任务被取消）清除
+struct UpdateGuard<'a>(&'a HostServerProcess);
+
+impl<'a> UpdateGuard<'a> {
+    fn acquire(state: &'a HostServerProcess) -> Result<Self, String> {
+        let mut guard = state.0.lock().unwrap();
+        if guard.updating {
+            return Err("A host_server update is already in progress".into());
+        }
+        guard.updating = true;
+        Ok(UpdateGuard(state))
+    }
+}
+
+impl Drop for UpdateGuard<'_> {
+    fn drop(&mut self) {
+        self.0 .0.lock().unwrap().updating = false;
+    }
+}
+
+/// 检查发布源，有新版本时安装并切换；新版本未能按时 ready 则回滚到之前的版本
+///
+/// 启动检查与前端手动更新可能同时触发，同一时间只允许一次，后到的直接返回错误。
+pub async fn check_and_apply<H: Host>(
+    app: &H,
+) -> Result<Option<HostServerUpdate>, String> {
+    if host_server::external_url().is_some() {
+        return Err("External host_server is in use, skipping update".into());
+    }
+    let _updating = UpdateGuard::acquire(app.host_server())?;
+    let config = app.app_config();
+    let update_dir = get_update_dir(&config).ok_or("Failed to get host_server update dir")?;
+    let url = feed_url().ok_or("No host_server release feed configured")?;
+    let feed = fetch_feed(&url).await?;
+    let asset = feed
+        .platforms
+        .get(host_server::binary_name())
+        .ok_or_else(|| format!("Release feed has no {}", host_server::binary_name()))?;
+
+    let current = current_version(app);
+    let latest = parse_version(&feed.version)
+        .ok_or_else(|| format!("Invalid version in release feed: {}", feed.version))?;
+    // 目录名使用规范化的版本号，不直接使用发布源中的字符串
+    let version = latest.to_string();
+    if let Some(current) = current.as_deref().and_then(parse_version) {
+        if latest <= current {
+            log::info!("host_server is up to date ({})", current);
+            return Ok(None);
+        }
+    }
+
+    install(&update_dir, &version, asset, HOST_SERVER_UPDATE_PUBKEY).await?;
+    let state = app.host_server();
+    let relaunch = || async {
+        host_server::relaunch_host_server(app, state, HOST_SERVER_UPDATE_READY_TIMEOUT)
+            .await
+            .map(|_| ())
+    };
+    let update = activate(&update_dir, current, &version, relaunch).await?;
+
+    if let Err(e) = app.emit_event(HOST_SERVER_UPDATED_EVENT, update.clone()) {
+        log::error!("Failed to emit {} to frontend: {}", HOST_SERVER_UPDATED_EVENT, e);
+    }
+    Ok(Some(update))
+}
+
+/// 启动时在后台检查一次更新；未配置发布源时什么也不做
+pub async fn check_on_startup<H: Host>(app: H) {
+    if feed_url().is_none() || host_server::external_url().is_some() {
+        return;
+    }
+    match check_and_apply(&app).await {
+        Ok(Some(update)) if update.rolled_back => {
+            log::warn!("host_server update to {} was rolled back", update.to)
+        }
+        Ok(Some(update)) => log::info!("host_server updated to {}", update.to),
+        Ok(None) => {}
+        Err(e) => log::warn!("host_server update check failed: {}", e),
+    }
+}
+
+/// 检查并应用 host_server 独立更新；已是最新时返回 None
+#[tauri::command]
+pub async fn update_host_server(app: AppHandle) -> Result<Option<HostServerUpdate>, String> {
+    check_and_apply(&app).await
+}
+
+/// 当前生效的 host_server 版本信息
+#[tauri::command]
+pub fn get_host_server_version(app: AppHandle) -> HostServerVersion {
+    let pointer = load_active(&app.config());
+    HostServerVersion {
+        version: current_version(&app),
+        updated: pointer.version.is_some(),
+        previous: pointer.previous,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::HOST_SERVER_MANIFEST_NAME;
+    use base64::engine::general_purpose::STANDARD;
+    use ed25519_compact::{KeyPair, Seed};
+    use sha2::{Digest, Sha256};
+    use std::cell::Cell;
+    use std::collections::HashMap;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    const KEY_ID: [u8; 8] = [1, 2, 3, 4, 5, 6, 7, 8];
+
+    fn key_pair(seed: u8) -> KeyPair {
+        KeyPair::from_seed(Seed::new([seed; 32]))
+    }
+
+    /// minisign 公钥文件内容的 base64，与 HOST_SERVER_UPDATE_PUBKEY 格式相同
+    fn public_key(keys: &KeyPair) -> String {
+        let mut raw = b"Ed".to_vec();
+        raw.extend_from_slice(&KEY_ID);
+        raw.extend_from_slice(keys.pk.as_ref());
+        STANDARD.encode(format!("untrusted comment: test key\n{}\n", STANDARD.encode(raw)))
+    }
+
+    /// legacy 格式（直接签原始数据）的 minisign 签名，编码方式与发布源中的 signature 相同
+    fn sign(keys: &KeyPair, data: &[u8]) -> String {
+        let signature = keys.sk.sign(data, None);
+        let mut raw = b"Ed".to_vec();
+        raw.extend_from_slice(&KEY_ID);
+        raw.extend_from_slice(signature.as_ref());
+        let trusted = "timestamp:0";
+        let mut global = signature.as_ref().to_vec();
+        global.extend_from_slice(trusted.as_bytes());
+        let global = keys.sk.sign(&global, None);
+        STANDARD.encode(format!(
+            "untrusted comment: test signature\n{}\ntrusted comment: {}\n{}\n",
+            STANDARD.encode(raw),
+            trusted,
+            STANDARD.encode(global.as_ref())
+        ))
+    }
+
+    /// 与发布包结构相同的 zip：host_server_<os>/ 目录下的二进制与清单
+    fn release_zip(version: &str) -> Vec<u8> {
+        let name = host_server::binary_name();
+        let file = if cfg!(target_os = "windows") {
+            format!("{}.exe", name)
+        } else {
+            name.to_string()
+        };
+        let manifest = integrity::Manifest {
+            version: format!("v{}", version),
+            sha256: format!("{:x}", Sha256::digest(b"binary")),
+        };
+        let mut buf = std::io::Cursor::new(Vec::new());
+        let mut zip = zip::ZipWriter::new(&mut buf);
+        let options = zip::write::SimpleFileOptions::default();
+        zip.start_file(format!("{}/{}", name, file), options).unwrap();
+        zip.write_all(b"binary").unwrap();
+        zip.start_file(format!("{}/{}", name, HOST_SERVER_MANIFEST_NAME), options)
+            .unwrap();
+        zip.write_all(serde_json::to_string(&manifest).unwrap().as_bytes())
+            .unwrap();
+        zip.finish().unwrap();
+        buf.into_inner()
+    }
+
+    /// 在本机随机端口上按路径返回固定内容，返回基础地址
+    async fn serve(files: HashMap<String, Vec<u8>>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut request = Vec::new();
+                let mut buf = [0u8; 1024];
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    match stream.read(&mut buf).await {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => request.extend_from_slice(&buf[..n]),
+                    }
+                }
+                let request = String::from_utf8_lossy(&request);
+                let path = request.split_whitespace().nth(1).unwrap_or("/");
+                let (status, body) = match files.get(path) {
+                    Some(body) => ("200 OK", body.clone()),
+                    None => ("404 Not Found", Vec::new()),
+                };
+                let head = format!(
+                    "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    status,
+                    body.len()
+                );
+                let _ = stream.write_all(head.as_bytes()).await;
+                let _ = stream.write_all(&body).await;
+                let _ = stream.shutdown().await;
+            }
+        });
+        format!("http://127.0.0.1:{}", port)
+    }
+
+    /// 发布一个版本：返回发布源地址；sha256 与签名可以被篡改
+    async fn publish(version: &str, sha256: Option<&str>, signer: &KeyPair) -> String {
+        let zip = release_zip(version);
+        let sha256 = sha256
+            .map(String::from)
+            .unwrap_or_else(|| format!("{:x}", Sha256::digest(&zip)));
+        let signature = sign(signer, &zip);
+        let feed_path = "/feed.json".to_string();
+        let zip_path = format!("/host_server-{}.zip", version);
+        let mut files = HashMap::new();
+        files.insert(zip_path.clone(), zip);
+        let base = serve(files).await;
+        let feed = serde_json::json!({
+            "version": format!("v{}", version),
+            "platforms": {
+                host_server::binary_name(): {
+                    "url": format!("{}{}", base, zip_path),
+                    "sha256": sha256,
+                    "signature": signature,
+                }
+            }
+        });
+        // 发布源单独起一个服务，地址里已经包含 zip 的端口
+        let mut files = HashMap::new();
+        files.insert(feed_path.clone(), feed.to_string().into_bytes());
+        format!("{}{}", serve(files).await, feed_path)
+    }
+
+    fn update_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("aiden-update-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("versions")).unwrap();
+        dir
+    }
+
+    async fn fetch_asset(url: &str) -> ReleaseAsset {
+        let feed = fetch_feed(url).await.unwrap();
+        feed.platforms[host_server::binary_name()].clone()
+    }
+
+    #[tokio::test]
+    async fn install_and_switch() {
+        let keys = key_pair(7);
+        let url = publish("0.2.42", None, &keys).await;
+        let dir = update_dir("install");
+        let asset = fetch_asset(&url).await;
+
+        install(&dir, "0.2.42", &asset, &public_key(&keys)).await.unwrap();
+        assert!(binary_in(&version_dir(&dir, "0.2.42")).is_file());
+        assert!(!dir.join("0.2.42.zip").exists());
+
+        let update = activate(&dir, Some("v0.2.41".into()), "0.2.42", || async { Ok(()) })
+            .await
+            .unwrap();
+        assert!(!update.rolled_back);
+        let active = read_active(&dir);
+        assert_eq!(active.version.as_deref(), Some("0.2.42"));
+        assert_eq!(active.previous, None);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn sha_mismatch_is_rejected() {
+        let keys = key_pair(7);
+        let url = publish("0.2.42", Some(&"0".repeat(64)), &keys).await;
+        let dir = update_dir("sha");
+        let asset = fetch_asset(&url).await;
+
+        let err = install(&dir, "0.2.42", &asset, &public_key(&keys)).await.unwrap_err();
+        assert!(err.contains("sha256 mismatch"), "{}", err);
+        assert!(!version_dir(&dir, "0.2.42").exists());
+        assert!(!dir.join("0.2.42.staging").exists());
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn foreign_signature_is_rejected() {
+        let url = publish("0.2.42", None, &key_pair(9)).await;
+        let dir = update_dir("signature");
+        let asset = fetch_asset(&url).await;
+
+        let err = install(&dir, "0.2.42", &asset, &public_key(&key_pair(7)))
+            .await
+            .unwrap_err();
+        assert!(err.contains("signature"), "{}", err);
+        assert!(!version_dir(&dir, "0.2.42").exists());
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn rollback_when_not_ready() {
+        let keys = key_pair(7);
+        let url = publish("0.2.42", None, &keys).await;
+        let dir = update_dir("rollback");
+        save_active(
+            &dir,
+            &ActivePointer {
+                version: Some("0.2.41".into()),
+                previous: None,
+            },
+        )
+        .unwrap();
+        let asset = fetch_asset(&url).await;
+        install(&dir, "0.2.42", &asset, &public_key(&keys)).await.unwrap();
+
+        let launches = Cell::new(0);
+        let relaunch = || {
+            launches.set(launches.get() + 1);
+            let first = launches.get() == 1;
+            async move {
+                if first {
+                    Err("not ready".to_string())
+                } else {
+                    Ok(())
+                }
+            }
+        };
+        let update = activate(&dir, Some("0.2.41".into()), "0.2.42", relaunch)
+            .await
+            .unwrap();
+        assert!(update.rolled_back);
+        assert_eq!(update.error.as_deref(), Some("not ready"));
+        assert_eq!(launches.get(), 2);
+        assert_eq!(read_active(&dir).version.as_deref(), Some("0.2.41"));
+        assert!(!version_dir(&dir, "0.2.42").exists());
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn bundled_public_key_is_valid() {
+        let data = b"host_server";
+        let err = verify_signature(HOST_SERVER_UPDATE_PUBKEY, data, &sign(&key_pair(7), data))
+            .unwrap_err();
+        assert!(err.contains("verification failed"), "{}", err);
+    }
+
+    #[test]
+    fn only_one_update_at_a_time() {
+        let state = HostServerProcess(std::sync::Mutex::new(Default::default()));
+        let first = UpdateGuard::acquire(&state).unwrap();
+        assert!(UpdateGuard::acquire(&state).is_err());
+        drop(first);
+        assert!(UpdateGuard::acquire(&state).is_ok());
+    }
+
+    #[test]
+    fn only_https_or_local_http() {
+        assert!(parse_url("https://example.com/feed.json").is_ok());
+        // 只有 debug 构建接受本机 http
+        assert_eq!(
+            parse_url("http://127.0.0.1:8080/feed.json").is_ok(),
+            cfg!(debug_assertions)
+        );
+        assert!(parse_url("http://example.com/feed.json").is_err());
+        assert!(parse_url("ftp://example.com/feed.json").is_err());
+    }
+}
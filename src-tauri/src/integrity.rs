@@ -0,0 +1,195 @@
+use crate::constants::{HOST_SERVER_MANIFEST_NAME, HOST_SERVER_VERSION_TIMEOUT};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Mutex;
+use tokio::process::Command as TokioCommand;
+
+#[cfg(target_os = "windows")]
+use crate::constants::CREATE_NO_WINDOW;
+
+/// 本进程内 `--version` 已通过的二进制 sha256；host_server 重启时不再重复运行，失败的不缓存
+static VERSION_CHECKED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// 由 .github/scripts/host-server-manifest.sh 生成（发布包在 macOS 重新签名之后），与 host_server 二进制放在同一目录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    /// 来自 .host_server_version，如 v0.2.41
+    pub version: String,
+    pub sha256: String,
+}
+
+/// 校验失败的原因，作为 host_server_failed 事件的一部分发给前端
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum VerifyError {
+    BinaryMissing { path: String },
+    BinaryUnreadable { path: String, message: String },
+    ManifestMissing { path: String },
+    ManifestInvalid { path: String, message: String },
+    ChecksumMismatch { expected: String, actual: String },
+    VersionCheckFailed { message: String },
+    VersionMismatch { expected: String, actual: String },
+}
+
+impl std::fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VerifyError::BinaryMissing { path } => write!(f, "host_server binary not found: {}", path),
+            VerifyError::BinaryUnreadable { path, message } => {
+                write!(f, "cannot read host_server binary {}: {}", path, message)
+            }
+            VerifyError::ManifestMissing { path } => write!(f, "manifest not found: {}", path),
+            VerifyError::ManifestInvalid { path, message } => {
+                write!(f, "invalid manifest {}: {}", path, message)
+            }
+            VerifyError::ChecksumMismatch { expected, actual } => {
+                write!(f, "sha256 mismatch: expected {}, got {}", expected, actual)
+            }
+            VerifyError::VersionCheckFailed { message } => {
+                write!(f, "`--version` failed: {}", message)
+            }
+            VerifyError::VersionMismatch { expected, actual } => {
+                write!(f, "version mismatch: expected {}, got {:?}", expected, actual)
+            }
+        }
+    }
+}
+
+pub fn manifest_path(binary_path: &Path) -> PathBuf {
+    binary_path
+        .parent()
+        .map(|dir| dir.join(HOST_SERVER_MANIFEST_NAME))
+        .unwrap_or_else(|| PathBuf::from(HOST_SERVER_MANIFEST_NAME))
+}
+
+pub fn load_manifest(binary_path: &Path) -> Result<Manifest, VerifyError> {
+    let path = manifest_path(binary_path);
+    let display = path.to_string_lossy().to_string();
+    if !path.exists() {
+        return Err(VerifyError::ManifestMissing { path: display });
+    }
+    std::fs::read_to_string(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|text| serde_json::from_str(&text).map_err(|e| e.to_string()))
+        .map_err(|message| VerifyError::ManifestInvalid {
+            path: display,
+            message,
+        })
+}
+
+pub fn sha256_file(path: &Path) -> std::io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
+
+/// 版本号比较时忽略前缀 v，输出中任意一个词匹配即可（如 "host_server 0.2.41"）
+fn version_matches(expected: &str, output: &str) -> bool {
+    let expected = expected.trim().trim_start_matches('v');
+    output
+        .split_whitespace()
+        .any(|word| word.trim_start_matches('v') == expected)
+}
+
+/// 运行 `host_server --version`，返回输出的第一行
+async fn run_version(binary_path: &Path, env: &BTreeMap<String, String>) -> Result<String, String> {
+    let mut cmd = TokioCommand::new(binary_path);
+    cmd.arg("--version")
+        .env_clear()
+        .envs(env)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    #[cfg(target_os = "windows")]
+    cmd.creation_flags(CREATE_NO_WINDOW);
+
+    let output = tokio::time::timeout(HOST_SERVER_VERSION_TIMEOUT, cmd.output())
+        .await
+        .map_err(|_| format!("timed out after {:?}", HOST_SERVER_VERSION_TIMEOUT))?
+        .map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(format!(
+            "exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(stdout.lines().next().unwrap_or_default().trim().to_string())
+}
+
+/// 启动前校验 host_server：sha256 与清单一致，且 `--version` 输出期望的版本
+///
+/// 每个二进制（按 sha256）在本进程内通过一次 `--version` 检查后，崩溃重启时不再重复运行。
+/// 开发模式下缺少清单只记录警告，方便使用本地构建的 host_server。
+pub async fn verify_host_server(
+    binary_path: &Path,
+    env: &BTreeMap<String, String>,
+) -> Result<(), VerifyError> {
+    if !binary_path.is_file() {
+        return Err(VerifyError::BinaryMissing {
+            path: binary_path.to_string_lossy().to_string(),
+        });
+    }
+    let manifest = match load_manifest(binary_path) {
+        Ok(manifest) => manifest,
+        Err(VerifyError::ManifestMissing { path }) if cfg!(debug_assertions) => {
+            log::warn!("No host_server manifest at {}, skipping verification", path);
+            return Ok(());
+        }
+        Err(e) => return Err(e),
+    };
+
+    let path = binary_path.to_path_buf();
+    let actual = tokio::task::spawn_blocking(move || sha256_file(&path))
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|result| result.map_err(|e| e.to_string()))
+        .map_err(|message| VerifyError::BinaryUnreadable {
+            path: binary_path.to_string_lossy().to_string(),
+            message,
+        })?;
+    if !actual.eq_ignore_ascii_case(manifest.sha256.trim()) {
+        return Err(VerifyError::ChecksumMismatch {
+            expected: manifest.sha256,
+            actual,
+        });
+    }
+
+    if !VERSION_CHECKED.lock().unwrap().contains(&actual) {
+        let output = run_version(binary_path, env)
+            .await
+            .map_err(|message| VerifyError::VersionCheckFailed { message })?;
+        if !version_matches(&manifest.version, &output) {
+            return Err(VerifyError::VersionMismatch {
+                expected: manifest.version,
+                actual: output,
+            });
+        }
+        VERSION_CHECKED.lock().unwrap().push(actual.clone());
+    }
+    log::info!(
+        "host_server verified: {} (sha256 {})",
+        manifest.version,
+        actual
+    );
+    Ok(())
+}
@@ -1,3 +1,4 @@
+use flexi_logger::{Duplicate, FileSpec, Logger, WriteMode};
 use std::{fs::File, io::Write, path::PathBuf};
 use tauri::api::path::{app_data_dir, download_dir};
 use tauri::{AppHandle, Config};
@@ -12,6 +13,32 @@ pub fn get_log_file_path(config: &Config) -> Option<PathBuf> {
     Some(path)
 }
 
+/// 初始化日志：写入日志文件，同时复制一份到终端
+///
+/// headless 模式下复制到 stderr，stdout 只输出 host_server 地址和密钥，方便脚本读取。
+pub fn init_logger(config: &Config, headless: bool) {
+    let log_file = get_log_file_path(config).expect("Failed to get log file path");
+    let log_dir = log_file.parent().unwrap();
+    let log_basename = log_file.file_stem().unwrap().to_str().unwrap();
+    let log_suffix = log_file.extension().unwrap().to_str().unwrap();
+
+    let file_spec = FileSpec::default()
+        .directory(log_dir)
+        .basename(log_basename)
+        .suffix(log_suffix);
+
+    let logger = Logger::try_with_str("info")
+        .unwrap()
+        .log_to_file(file_spec)
+        .write_mode(WriteMode::BufferAndFlush);
+    let logger = if headless {
+        logger.duplicate_to_stderr(Duplicate::Info)
+    } else {
+        logger.duplicate_to_stdout(Duplicate::Info)
+    };
+    logger.start().unwrap();
+}
+
 /// 导出日志为 zip 文件，返回 zip 文件的路径
 pub fn export_log_zip(app: AppHandle) -> Result<String, String> {
     let config = app.config();
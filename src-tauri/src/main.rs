@@ -1,34 +1,42 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
-#[cfg(target_os = "windows")]
-use std::os::windows::process::CommandExt;
-#[cfg(target_os = "windows")]
-const CREATE_NO_WINDOW: u32 = 0x08000000;
 
 mod cleanup;
+mod cli;
+mod config;
+mod config_history;
 mod constants;
+mod headless;
+mod health;
+mod host;
+mod host_env;
+mod host_log;
+mod host_server;
+mod host_update;
+mod integrity;
 mod logger;
 mod mcp;
+mod mcp_probe;
+mod mcp_server;
 mod agent;
+mod ports;
+mod process;
+mod process_stats;
 mod request;
+#[cfg(target_os = "linux")]
+mod run_state;
+mod schema;
+mod shell_env;
+mod single_instance;
 mod stream;
 
-use crate::constants::{HOST_SERVER_EVENT_NAME, HOST_SERVER_READY_TEXT, PORTS_TO_KILL};
-use flexi_logger::{Duplicate, FileSpec, Logger, WriteMode};
+use crate::constants::{HOST_SERVER_STOP_TIMEOUT, PORTS_TO_CHECK};
+use crate::host_server::{HostServerProcess, HostServerState};
+use crate::single_instance::Instance;
 use sentry;
-use std::env;
-use std::net::TcpListener;
-use std::path::PathBuf;
-use std::process::{Command as StdCommand, Stdio as StdStdio};
 use std::sync::Mutex;
-use tauri::api::path::resource_dir;
-use tauri::{AppHandle, Manager, Runtime, State};
+use tauri::{AppHandle, Manager, State};
 #[cfg(target_os = "macos")]
 use tauri::{CustomMenuItem, Menu, MenuItem, Submenu};
-use tokio::io::AsyncBufReadExt;
-use tokio::process::{Child, Command as TokioCommand};
-use tokio::task;
-
-pub struct HostServerProcess(pub Mutex<Option<Child>>);
 
 // Tauri command to handle logs from the frontend
 #[tauri::command]
@@ -43,264 +51,14 @@ fn log_from_frontend(level: String, message: String) {
     }
 }
 
-fn get_env_path<R: Runtime>(app: &AppHandle<R>) -> Option<PathBuf> {
-    if cfg!(debug_assertions) {
-        Some(
-            std::env::current_dir()
-                .ok()
-                .map(|dir: PathBuf| dir.join("../.env"))?,
-        )
-    } else {
-        resource_dir(app.package_info(), &app.env()).map(|dir: PathBuf| dir.join("bin/.env"))
-    }
-}
-
-fn get_host_server_path<R: Runtime>(app: &AppHandle<R>) -> PathBuf {
-    #[cfg(target_os = "macos")]
-    let binary_name = "host_server_macos";
-
-    #[cfg(target_os = "linux")]
-    let binary_name = "host_server_linux";
-
-    #[cfg(target_os = "windows")]
-    let binary_name = "host_server_windows";
-
-    #[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "windows")))]
-    let binary_name = "host_server_macos";
-
-    if cfg!(debug_assertions) {
-        std::env::current_dir()
-            .unwrap()
-            .join(format!("../node_modules/{}/{}", binary_name, binary_name))
-    } else {
-        resource_dir(app.package_info(), &app.env())
-            .unwrap()
-            .join(format!("resources/{}/{}", binary_name, binary_name))
-    }
-}
-
-fn get_shell_path() -> Option<String> {
-    let output = StdCommand::new("/bin/zsh")
-        .args(&["-ilc", "env"])
-        .output()
-        .ok()?;
-    let env_str = String::from_utf8_lossy(&output.stdout);
-    for line in env_str.lines() {
-        if let Some(path) = line.strip_prefix("PATH=") {
-            return Some(path.to_string());
-        }
-    }
-    None
-}
-
-fn append_bin_to_path<R: Runtime>(app: &AppHandle<R>) -> String {
-    let bin_dir = if cfg!(debug_assertions) {
-        std::env::current_dir().unwrap().join("bin")
-    } else {
-        resource_dir(app.package_info(), &app.env())
-            .unwrap()
-            .join("bin")
-    };
-    let bin_dir_str = bin_dir.to_string_lossy();
-    let shell_path = get_shell_path().unwrap_or_else(|| std::env::var("PATH").unwrap_or_default());
-    let sep: &'static str = if cfg!(target_os = "windows") {
-        ";"
-    } else {
-        ":"
-    };
-    format!("{}{}{}", bin_dir_str, sep, shell_path)
-}
-
-fn find_free_port() -> Option<u16> {
-    TcpListener::bind("127.0.0.1:0")
-        .ok()
-        .and_then(|listener| listener.local_addr().ok())
-        .map(|addr| addr.port())
-}
-
-fn start_host_server<R: Runtime>(app: &AppHandle<R>, state: State<HostServerProcess>) {
-    let binary_path: PathBuf = get_host_server_path(app);
-    let config = app.config();
-    let mcp_config_path = mcp::get_user_config_path(&config).expect("Cannot get MCP config path");
-    let agent_config_path = agent::get_user_config_path(&config).expect("Cannot get Agent config path");
-    log::info!("Starting host server from: {:?}", binary_path);
-    log::info!("Using config file from: {:?}", mcp_config_path);
-    log::info!("Using agent config file from: {:?}", agent_config_path);
-
-    let new_path = append_bin_to_path(app);
-    log::info!("Setting PATH to host_server: {}", new_path);
-
-    let port = if cfg!(debug_assertions) {
-        log::info!("Development mode, using fixed port 6888");
-        6888
-    } else {
-        let free_port = find_free_port().expect("Failed to find a free port");
-        log::info!("Production mode, using dynamic port: {}", free_port);
-        free_port
-    };
-
-    #[cfg(target_os = "windows")]
-    let mut child: tokio::process::Child = {
-        let mut cmd = std::process::Command::new(binary_path.clone());
-        let mcp_config_str = mcp_config_path.to_string_lossy().to_string();
-        let agent_config_str = agent_config_path.to_string_lossy().to_string();
-        let port_str = port.to_string();
-
-        let mut args = vec![
-            "--config_file".to_string(),
-            mcp_config_str,
-            "--agent_config_file".to_string(),
-            agent_config_str,
-            "--disable_reload".to_string(),
-            "--enable_authorization".to_string(),
-            "--port".to_string(),
-            port_str,
-        ];
-
-        if cfg!(debug_assertions) {
-            args.push("--is_dev".into());
-        }
-        
-        cmd.args(&args)
-        .envs(env::vars())
-        .env("PATH", &new_path)
-        .stdout(StdStdio::piped())
-        .stderr(StdStdio::piped())
-        .creation_flags(CREATE_NO_WINDOW);
-        tokio::process::Command::from(cmd)
-            .spawn()
-            .expect("Failed to start host_server (windows)")
-    };
-
-    #[cfg(not(target_os = "windows"))]
-    let mut child: tokio::process::Child = {
-        let mut cmd = TokioCommand::new(binary_path.to_string_lossy().to_string());
-        let mut args = vec![
-            "--config_file".into(),
-            mcp_config_path.to_string_lossy().to_string(),
-            "--agent_config_file".into(),
-            agent_config_path.to_string_lossy().to_string(),
-            "--disable_reload".into(),
-            "--enable_authorization".into(),
-            "--port".into(),
-            port.to_string(),
-        ];
-
-        if cfg!(debug_assertions) {
-            args.push("--is_dev".into());
-        }
-
-        cmd.args(args)
-            .envs(env::vars())
-            .env("PATH", new_path)
-            .stdout(StdStdio::piped())
-            .stderr(StdStdio::piped())
-            .spawn()
-            .expect("Failed to start host_server")
-    };
-
-    let stdout = child.stdout.take().expect("Failed to capture stdout");
-    let stderr = child.stderr.take().expect("Failed to capture stderr");
-
-    *state.0.lock().unwrap() = Some(child);
-
-    // stdout 日志 + ready 检测
-    let app_clone = app.clone();
-    task::spawn(async move {
-        let reader = tokio::io::BufReader::new(stdout);
-        let mut lines = reader.lines();
-        while let Ok(Some(line)) = lines.next_line().await {
-            log::info!("host_server stdout: {}", line);
-            let expected_ready_text = format!("{}{}", HOST_SERVER_READY_TEXT, port);
-            if line.contains(&expected_ready_text) {
-                if let Err(e) = app_clone.emit_all(HOST_SERVER_EVENT_NAME, port) {
-                    log::error!("Failed to emit event to frontend: {}", e);
-                }
-            }
-        }
-    });
-
-    // stderr 日志 + sentry 上报
-    task::spawn(async move {
-        let reader = tokio::io::BufReader::new(stderr);
-        let mut lines = reader.lines();
-        while let Ok(Some(line)) = lines.next_line().await {
-            log::error!("host_server stderr: {}", line);
-            // sentry::capture_message(
-            //     &format!("host_server stderr: {}", line),
-            //     sentry::Level::Error,
-            // );
-        }
-    });
-}
-
-fn kill_ports(ports: &[u16]) {
-    #[cfg(target_family = "unix")]
-    for port in ports {
-        log::info!("Checking port {}...", port);
-        let output: Result<std::process::Output, std::io::Error> = StdCommand::new("lsof")
-            .arg("-ti")
-            .arg(format!(":{}", port))
-            .output();
-
-        if let Ok(output) = output {
-            let pids = String::from_utf8_lossy(&output.stdout);
-            for pid in pids.lines() {
-                if !pid.trim().is_empty() {
-                    log::info!("Killing process {} on port {}", pid, port);
-                    let _ = StdCommand::new("kill").arg("-9").arg(pid).output();
-                }
-            }
-        }
+fn cleanup_processes(state: &HostServerProcess) {
+    // 由 supervisor 负责：先停止自动重启，再对 host_server 进程组 SIGTERM → SIGKILL
+    if host_server::external_url().is_some() {
+        log::info!("Using external host_server, leaving its processes alone");
+        return;
     }
-}
-
-fn cleanup_processes<R: Runtime>(_app: &AppHandle<R>, state: State<HostServerProcess>) {
-    let mut guard = state.0.lock().unwrap();
-    if guard.is_some() {
-        log::info!("Attempting to kill all related processes (host_server, aiden)...");
-        #[cfg(unix)]
-        {
-            use std::process::Command;
-            let patterns = ["host_server"];
-            for pattern in patterns.iter() {
-                let cmd = format!("pgrep -f '{}' | grep -v $$ | xargs -r kill -9", pattern);
-                log::info!("Executing cleanup command: {}", cmd);
-
-                let output = Command::new("sh").arg("-c").arg(&cmd).output();
-                log::info!("Command executed, checking output...");
-
-                match output {
-                    Ok(out) => {
-                        if !out.stdout.is_empty() {
-                            log::info!(
-                                "Killed processes matching '{}':\n{}",
-                                pattern,
-                                String::from_utf8_lossy(&out.stdout)
-                            );
-                        } else {
-                            log::info!("No running processes matched '{}'", pattern);
-                        }
-                        if !out.stderr.is_empty() {
-                            log::warn!(
-                                "Stderr while killing '{}':\n{}",
-                                pattern,
-                                String::from_utf8_lossy(&out.stderr)
-                            );
-                        }
-                    }
-                    Err(e) => {
-                        log::error!(
-                            "Failed to execute cleanup command for '{}': {:?}",
-                            pattern,
-                            e
-                        );
-                    }
-                }
-            }
-        }
-        *guard = None;
-    } else {
+    log::info!("Stopping host_server...");
+    if !host_server::stop_supervisor(state, HOST_SERVER_STOP_TIMEOUT) {
         log::warn!("No host_server process was running.");
     }
 }
@@ -314,6 +72,28 @@ fn export_log_zip_cmd(app: tauri::AppHandle) -> Result<String, String> {
 async fn main() {
     let is_prod = !cfg!(debug_assertions);
 
+    let context = tauri::generate_context!();
+
+    // ---- 命令行子命令（aiden mcp / agent / config）：只处理配置文件，不启动 GUI ----
+    if let Some(code) = cli::run(context.config(), context.package_info()) {
+        std::process::exit(code);
+    }
+
+    // headless 的地址和 token 输出到 stdout，同样需要控制台
+    if headless::requested() {
+        cli::attach_parent_console();
+    }
+
+    // ---- 单实例：已有实例运行时转发参数后直接退出 ----
+    let instance = match single_instance::acquire(context.config()) {
+        Ok(Instance::Primary(instance)) => Some(instance),
+        Ok(Instance::Secondary) => return,
+        Err(e) => {
+            eprintln!("Single instance check failed, continuing anyway: {}", e);
+            None
+        }
+    };
+
     let _sentry_guard = if is_prod {
         Some(sentry::init((
             "https://6598ac3fc1e35c15c14cbd94e7a73b3b@sentry.aidenai.io/3",
@@ -327,9 +107,27 @@ async fn main() {
         None
     };
 
+    // ---- Headless：不创建 webview，前台运行直到 SIGTERM ----
+    if headless::requested() {
+        let code = headless::run(
+            context.config().clone(),
+            context.package_info().clone(),
+            instance,
+        )
+        .await;
+        if let Some(sentry_guard) = _sentry_guard {
+            sentry_guard.flush(Some(std::time::Duration::from_secs(2)));
+        }
+        std::process::exit(code);
+    }
+
     // ---- Tauri Builder ----
     let mut builder = tauri::Builder::default()
-        .manage(HostServerProcess(Mutex::new(None)))
+        .manage(HostServerProcess(Mutex::new(HostServerState::default())))
+        .manage(ports::PortConflicts(Mutex::new(Vec::new())))
+        .manage(mcp_probe::McpRuntimesMissing(Mutex::new(Vec::new())))
+        .manage(host_log::HostServerLogs::default())
+        .manage(process_stats::ProcessMonitor::default())
         .invoke_handler(tauri::generate_handler![
             log_from_frontend,
             export_log_zip_cmd,
@@ -337,8 +135,33 @@ async fn main() {
             request::fetch_no_proxy,
             mcp::read_mcp_config,
             mcp::write_mcp_config,
+            mcp_probe::probe_mcp_runtimes,
+            mcp_probe::get_missing_mcp_runtimes,
             agent::read_agent_config,
             agent::write_agent_config,
+            config::validate_config,
+            config::get_config_schema,
+            config_history::list_config_snapshots,
+            config_history::diff_config_snapshot,
+            config_history::restore_config_snapshot,
+            host_server::host_server_status,
+            host_server::start_host_server,
+            host_server::stop_host_server,
+            host_server::restart_host_server,
+            host_server::get_host_server_secret,
+            host_log::get_host_server_logs,
+            host_log::watch_host_server_logs,
+            process_stats::process_stats,
+            process_stats::watch_process_stats,
+            host_update::update_host_server,
+            host_update::get_host_server_version,
+            ports::get_port_conflicts,
+            host_env::read_env_overrides,
+            host_env::write_env_overrides,
+            host_env::read_env_allowlist,
+            host_env::write_env_allowlist,
+            host_env::get_mcp_server_envs,
+            shell_env::get_shell_path_info,
         ])
         // 监听窗口关闭事件
         .on_window_event(|event| {
@@ -393,42 +216,44 @@ async fn main() {
     let app = builder
         .setup(|app: &mut tauri::App| {
             let config: std::sync::Arc<tauri::Config> = app.config();
-            let log_file = logger::get_log_file_path(&config).expect("Failed to get log file path");
-            let log_dir = log_file.parent().unwrap();
-            let log_basename = log_file.file_stem().unwrap().to_str().unwrap();
-            let log_suffix = log_file.extension().unwrap().to_str().unwrap();
-
-            let file_spec = FileSpec::default()
-                .directory(log_dir)
-                .basename(log_basename)
-                .suffix(log_suffix);
-
-            Logger::try_with_str("info")
-                .unwrap()
-                .log_to_file(file_spec)
-                .duplicate_to_stdout(Duplicate::Info)
-                .write_mode(WriteMode::BufferAndFlush)
-                .start()
-                .unwrap();
+            logger::init_logger(&config, false);
 
             log::info!("AidenAI started successfully!");
-            mcp::init_mcp_config(app).expect("Failed to init MCP config");
-            agent::init_agent_config(app).expect("Failed to init Agent config");
-            cleanup::cleanup_database(&config);
-            kill_ports(PORTS_TO_KILL);
             let app_handle: AppHandle = app.handle();
+            if let Some(instance) = instance {
+                instance.listen(app_handle.clone());
+            }
+            if let Err(e) = mcp::init_mcp_config(&app_handle) {
+                log::error!("Failed to init MCP config: {}", e);
+            }
+            if let Err(e) = agent::init_agent_config(&app_handle) {
+                log::error!("Failed to init Agent config: {}", e);
+            }
+            cleanup::cleanup_database(&config);
+            // 外部 host_server 模式下进程由开发者管理，不清理遗留进程也不检查端口
+            if host_server::external_url().is_none() {
+                // 先结束上次遗留的进程，端口检查只报告剩下的占用者
+                #[cfg(target_os = "linux")]
+                cleanup::sweep_orphans(&config);
+                ports::report_port_conflicts(&app_handle, PORTS_TO_CHECK);
+            }
             let state: State<'_, HostServerProcess> = app.state::<HostServerProcess>();
-            start_host_server(&app_handle, state);
+            if let Err(e) = host_server::launch_host_server(&app_handle, &state) {
+                log::error!("Failed to launch host_server: {}", e);
+            }
+            tauri::async_runtime::spawn(mcp_probe::warn_missing_runtimes(app_handle.clone()));
+            tauri::async_runtime::spawn(process_stats::monitor_thresholds(app_handle.clone()));
+            tauri::async_runtime::spawn(host_update::check_on_startup(app_handle));
             Ok(())
         })
-        .build(tauri::generate_context!())
+        .build(context)
         .expect("error while running tauri application");
 
     app.run(|app_handle, event| {
         if let tauri::RunEvent::Exit { .. } = event {
             log::info!("App is exiting — now cleaning processes");
             let state: State<'_, HostServerProcess> = app_handle.state::<HostServerProcess>();
-            cleanup_processes(&app_handle, state);
+            cleanup_processes(&state);
         }
     });
 
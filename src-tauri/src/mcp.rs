@@ -1,9 +1,13 @@
-use semver::Version;
+use crate::config::{self, ConfigKind};
+use crate::config_history::{self, SnapshotReason};
+use crate::host::Host;
+use crate::mcp_server::{self, FieldError, McpServer};
+use crate::schema::{self, Diagnostic, JsonSchema};
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
 
-use std::fs;
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use tauri::{api::path::app_data_dir, AppHandle, Config};
 
 /// eg: ~/Library/Application Support/com.aiden.chat/Config/mcp.config.json
@@ -23,149 +27,330 @@ pub fn get_user_config_path_from_app(app: &tauri::AppHandle) -> Option<PathBuf>
 pub struct MCPConfig {
     pub version: String,
     pub syncVersion: String,
-    pub mcpServers: serde_json::Map<String, serde_json::Value>,
+    pub mcpServers: BTreeMap<String, McpServer>,
     pub a2aServers: Option<serde_json::Value>,
+    /// 校验失败、读取时跳过的 server 原文，随 read_mcp_config 返回给前端显示与删除；
+    /// 保存时只写回这里仍保留的条目，调用方删掉的不再写回
+    #[serde(default, skip_deserializing, skip_serializing_if = "BTreeMap::is_empty")]
+    pub invalid: BTreeMap<String, Value>,
+    /// 跳过的 server 的字段错误，随 read_mcp_config 返回给前端提示；不写入文件
+    #[serde(default, skip_deserializing, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+}
+
+impl MCPConfig {
+    /// 解析整份配置；只有顶层结构错误时失败，单个 server 的错误记入 errors 并跳过该 server
+    pub fn from_value(value: Value) -> Result<MCPConfig, Vec<FieldError>> {
+        let mut errors = Vec::new();
+        let mut object = match value {
+            Value::Object(object) => object,
+            _ => {
+                return Err(vec![FieldError {
+                    server: None,
+                    field: String::new(),
+                    message: "must be an object".into(),
+                }])
+            }
+        };
+        let mut version_field = |field: &str| match object.remove(field) {
+            Some(Value::String(s)) => s,
+            _ => {
+                errors.push(FieldError {
+                    server: None,
+                    field: field.into(),
+                    message: "must be a string".into(),
+                });
+                String::new()
+            }
+        };
+        let version = version_field("version");
+        let sync_version = version_field("syncVersion");
+
+        let mut servers = BTreeMap::new();
+        let mut invalid = BTreeMap::new();
+        let mut server_errors = Vec::new();
+        match object.remove("mcpServers") {
+            Some(Value::Object(entries)) => {
+                for (name, entry) in entries {
+                    match McpServer::from_value(&name, &entry) {
+                        Ok(server) => {
+                            servers.insert(name, server);
+                        }
+                        Err(e) => {
+                            server_errors.extend(e);
+                            invalid.insert(name, entry);
+                        }
+                    }
+                }
+            }
+            None | Some(Value::Null) => {}
+            Some(_) => errors.push(FieldError {
+                server: None,
+                field: "mcpServers".into(),
+                message: "must be an object".into(),
+            }),
+        }
+
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        Ok(MCPConfig {
+            version,
+            syncVersion: sync_version,
+            mcpServers: servers,
+            a2aServers: object.remove("a2aServers").filter(|v| !v.is_null()),
+            invalid,
+            errors: server_errors,
+        })
+    }
+}
+
+impl JsonSchema for MCPConfig {
+    fn json_schema() -> Value {
+        schema::object(
+            vec![
+                ("version", String::json_schema()),
+                ("syncVersion", String::json_schema()),
+                ("mcpServers", BTreeMap::<String, McpServer>::json_schema()),
+                ("a2aServers", Option::<Value>::json_schema()),
+            ],
+            &["version", "syncVersion", "mcpServers"],
+        )
+    }
+}
+
+/// 与 read/write_mcp_config 相同的解析规则；未知字段会保留，仅提示
+pub fn validate_mcp_config(value: &Value) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = value
+        .as_object()
+        .into_iter()
+        .flat_map(|object| object.keys())
+        .filter(|key| !["version", "syncVersion", "mcpServers", "a2aServers"].contains(&key.as_str()))
+        .map(|key| Diagnostic::warning(key.as_str(), "unknown field, dropped on save"))
+        .collect();
+    match MCPConfig::from_value(value.clone()) {
+        Ok(config) => {
+            diagnostics.extend(
+                config
+                    .errors
+                    .iter()
+                    .map(|e| Diagnostic::error(e.path(), e.message.clone())),
+            );
+            for (name, server) in &config.mcpServers {
+                for field in server.extra.keys() {
+                    diagnostics.push(Diagnostic::warning(
+                        format!("mcpServers.{}.{}", name, field),
+                        format!(
+                            "unknown field for {} transport, kept as is",
+                            server.transport.name()
+                        ),
+                    ));
+                }
+            }
+        }
+        Err(errors) => diagnostics.extend(
+            errors
+                .iter()
+                .map(|e| Diagnostic::error(e.path(), e.message.clone())),
+        ),
+    }
+    diagnostics
+}
+
+/// write_mcp_config 的错误，errors 供前端定位到具体输入框
+#[derive(Debug, Serialize)]
+pub struct McpConfigError {
+    pub message: String,
+    pub errors: Vec<FieldError>,
+}
+
+impl From<String> for McpConfigError {
+    fn from(message: String) -> Self {
+        McpConfigError {
+            message,
+            errors: Vec::new(),
+        }
+    }
+}
+
+impl From<&str> for McpConfigError {
+    fn from(message: &str) -> Self {
+        McpConfigError::from(message.to_string())
+    }
+}
+
+/// 读取配置；无效的 server 被跳过，错误记录在 errors 中
+pub fn load_mcp_config(path: &Path) -> Result<MCPConfig, String> {
+    let value = config::read_config_file(path)?;
+    let config =
+        MCPConfig::from_value(value).map_err(|errors| mcp_server::describe_errors(&errors))?;
+    if !config.errors.is_empty() {
+        log::warn!(
+            "Skipped invalid MCP servers in {}: {}",
+            path.display(),
+            mcp_server::describe_errors(&config.errors)
+        );
+    }
+    Ok(config)
+}
+
+/// 校验后写入；config.invalid 中的无效 server 原样写回，同名的以新配置为准
+pub fn save_mcp_config(path: &Path, config: &MCPConfig) -> Result<(), String> {
+    let mut json = serde_json::to_value(config).map_err(|e| e.to_string())?;
+    if let Some(object) = json.as_object_mut() {
+        object.remove("errors");
+        object.remove("invalid");
+    }
+    config::check(ConfigKind::Mcp, &json)?;
+    if let Some(servers) = json.get_mut("mcpServers").and_then(Value::as_object_mut) {
+        for (name, entry) in &config.invalid {
+            servers.entry(name.clone()).or_insert_with(|| entry.clone());
+        }
+    }
+    config_history::snapshot(ConfigKind::Mcp, path, SnapshotReason::Write);
+    config::write_config_json(path, &json)
 }
 
 /// 读取配置
 #[tauri::command]
 pub fn read_mcp_config(app: AppHandle) -> Result<MCPConfig, String> {
     let path = get_user_config_path_from_app(&app).ok_or("配置路径不存在")?;
-    let contents = fs::read_to_string(&path).map_err(|e| e.to_string())?;
-    let config: MCPConfig = serde_json::from_str(&contents).map_err(|e| e.to_string())?;
-    Ok(config)
+    load_mcp_config(&path)
 }
 
-/// 写入配置
+/// 写入配置；传入的条目有错误时整份拒绝，不写入
+///
+/// 带 invalid 时只保留其中的无效 server（前端删掉的不再写回）；不带时保留文件中现有的全部无效 server。
 #[tauri::command]
-pub fn write_mcp_config(app: AppHandle, new_config: MCPConfig) -> Result<(), String> {
+pub fn write_mcp_config(app: AppHandle, mut new_config: Value) -> Result<(), McpConfigError> {
     let path = get_user_config_path_from_app(&app).ok_or("配置路径不存在")?;
-    let json_str = serde_json::to_string_pretty(&new_config).map_err(|e| e.to_string())?;
-    fs::write(&path, json_str).map_err(|e| e.to_string())?;
+    let kept_invalid = match new_config.as_object_mut().and_then(|object| object.remove("invalid")) {
+        Some(invalid) => serde_json::from_value(invalid)
+            .map_err(|e| format!("invalid must be an object of servers: {}", e))?,
+        None => load_mcp_config(&path)
+            .map(|current| current.invalid)
+            .unwrap_or_default(),
+    };
+    let mut new_config = MCPConfig::from_value(new_config)
+        .and_then(|config| {
+            if config.errors.is_empty() {
+                Ok(config)
+            } else {
+                Err(config.errors)
+            }
+        })
+        .map_err(|errors| McpConfigError {
+            message: mcp_server::describe_errors(&errors),
+            errors,
+        })?;
+    new_config.invalid = kept_invalid;
+    save_mcp_config(&path, &new_config)?;
     Ok(())
 }
 
-pub fn init_mcp_config(app: &tauri::App) -> Result<(), String> {
-    let config = app.config();
-    let user_config_path =
-        get_user_config_path(&config).ok_or("Failed to get MCP config file path.")?;
-
-    let default_path: PathBuf = app
-        .path_resolver()
-        .resolve_resource("resources/mcp.config.json")
-        .ok_or("Cannot find default MCP config in resources.")?;
-
-    // 首次安装，用户 config 不存在
-    if !user_config_path.exists() {
-        fs::copy(&default_path, &user_config_path)
-            .map_err(|e| format!("Copy MCP config failed: {}", e))?;
-        log::info!("MCP config initialized: {:?}", user_config_path);
-        return Ok(());
+pub fn init_mcp_config<H: Host>(app: &H) -> Result<(), String> {
+    config::init_config(app, ConfigKind::Mcp)
+}
+
+/// 升级时保留用户自己添加的 server，default 类型的 server 与 a2aServers 以默认配置为准
+pub fn merge_default_servers(user_json: &mut Value, default_json: &Value) {
+    let mut updated_servers =
+        if let Some(user_servers) = user_json.get("mcpServers").and_then(|v| v.as_object()) {
+            user_servers
+                .iter()
+                .filter(|(_, v)| v.get("aiden_type").map(|t| t != "default").unwrap_or(true))
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect::<Map<String, Value>>()
+        } else {
+            Map::new()
+        };
+
+    if let Some(default_servers) = default_json.get("mcpServers").and_then(|v| v.as_object()) {
+        for (k, v) in default_servers {
+            if v.get("aiden_type") == Some(&Value::String("default".into())) {
+                updated_servers.insert(k.clone(), v.clone());
+            }
+        }
     }
 
-    // 读取默认 config
-    let default_text = fs::read_to_string(&default_path)
-        .map_err(|e| format!("Failed to read default MCP config: {}", e))?;
-    let default_json: Value = serde_json::from_str(&default_text)
-        .map_err(|e| format!("Invalid JSON in default config: {}", e))?;
-
-    // 读取用户 config
-    let user_text = fs::read_to_string(&user_config_path)
-        .map_err(|e| format!("Failed to read user MCP config: {}", e))?;
-    let mut user_json: Value = serde_json::from_str(&user_text)
-        .map_err(|e: serde_json::Error| format!("Invalid JSON in user config: {}", e))?;
-
-    // ========= Step 1: 强制 syncVersion 同步判断 ============
-    let default_sync_str = default_json
-        .get("syncVersion")
-        .and_then(|v| v.as_str())
-        .unwrap_or("0.0.0");
-    let user_sync_str = user_json
-        .get("syncVersion")
-        .and_then(|v| v.as_str())
-        .unwrap_or("0.0.0");
-
-    let default_sync = Version::parse(default_sync_str).unwrap_or_else(|_| Version::new(0, 0, 0));
-    let user_sync = Version::parse(user_sync_str).unwrap_or_else(|_| Version::new(0, 0, 0));
-
-    log::info!(
-        "MCP config syncVersion: default={}, user={}",
-        default_sync,
-        user_sync
-    );
-
-    if default_sync > user_sync {
-        fs::copy(&default_path, &user_config_path)
-            .map_err(|e| format!("Forced sync copy failed: {}", e))?;
-        log::info!(
-            "MCP config forcibly synced due to syncVersion mismatch: {} -> {}",
-            user_sync,
-            default_sync
-        );
-        return Ok(());
+    user_json["mcpServers"] = Value::Object(updated_servers);
+
+    if let Some(default_a2a) = default_json.get("a2aServers") {
+        user_json["a2aServers"] = default_a2a.clone();
     }
+}
 
-    // ========= Step 2: 正常 version 增量更新逻辑 ============
-    let default_version_str = default_json
-        .get("version")
-        .and_then(|v| v.as_str())
-        .unwrap_or("");
-    let user_version_str = user_json
-        .get("version")
-        .and_then(|v: &Value| v.as_str())
-        .unwrap_or("");
-
-    log::info!(
-        "MCP config version: default={}, user={}",
-        default_version_str,
-        user_version_str
-    );
-
-    let default_version =
-        Version::parse(default_version_str).unwrap_or_else(|_| Version::new(0, 0, 0));
-    let user_version = Version::parse(user_version_str).unwrap_or_else(|_| Version::new(0, 0, 0));
-
-    if default_version > user_version {
-        log::info!(
-            "MCP config update needed: {} -> {}",
-            user_version,
-            default_version
-        );
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
 
-        let mut updated_servers =
-            if let Some(user_servers) = user_json.get("mcpServers").and_then(|v| v.as_object()) {
-                user_servers
-                    .iter()
-                    .filter(|(_, v)| v.get("aiden_type").map(|t| t != "default").unwrap_or(true))
-                    .map(|(k, v)| (k.clone(), v.clone()))
-                    .collect::<Map<String, Value>>()
-            } else {
-                Map::new()
-            };
+    #[test]
+    fn invalid_server_is_skipped() {
+        let config = MCPConfig::from_value(json!({
+            "version": "1.0.0",
+            "syncVersion": "1.0.0",
+            "mcpServers": {
+                "good": { "command": "npx" },
+                "remote": { "aiden_type": "remote", "aiden_id": "remote" },
+            },
+        }))
+        .unwrap();
+        assert!(config.mcpServers.contains_key("good"));
+        assert!(!config.mcpServers.contains_key("remote"));
+        assert!(config.invalid.contains_key("remote"));
+        assert_eq!(config.errors[0].server.as_deref(), Some("remote"));
+    }
 
-        if let Some(default_servers) = default_json.get("mcpServers").and_then(|v| v.as_object()) {
-            for (k, v) in default_servers {
-                if v.get("aiden_type") == Some(&Value::String("default".into())) {
-                    updated_servers.insert(k.clone(), v.clone());
-                }
-            }
-        }
+    #[test]
+    fn structural_errors_fail() {
+        assert!(MCPConfig::from_value(json!([])).is_err());
+        assert!(MCPConfig::from_value(json!({
+            "version": "1.0.0",
+            "syncVersion": "1.0.0",
+            "mcpServers": [],
+        }))
+        .is_err());
+    }
 
-        user_json["mcpServers"] = Value::Object(updated_servers);
-        user_json["version"] = Value::String(default_version.to_string());
+    #[test]
+    fn invalid_servers_are_kept_until_removed() {
+        let dir = std::env::temp_dir().join(format!("aiden-mcp-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("mcp.config.json");
+        let original = json!({
+            "version": "1.0.0",
+            "syncVersion": "1.0.0",
+            "mcpServers": {
+                "good": { "command": "npx" },
+                "broken": { "args": ["x"] },
+            },
+        });
+        std::fs::write(&path, original.to_string()).unwrap();
 
-        if let Some(default_a2a) = default_json.get("a2aServers") {
-            user_json["a2aServers"] = default_a2a.clone();
-        }
+        let read = |path: &Path| -> Value {
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+        };
 
-        fs::write(
-            &user_config_path,
-            serde_json::to_string_pretty(&user_json).unwrap(),
-        )
-        .map_err(|e| format!("Failed to write updated MCP config: {}", e))?;
+        // 无效条目返回给调用方
+        let mut config = load_mcp_config(&path).unwrap();
+        let returned = serde_json::to_value(&config).unwrap();
+        assert_eq!(returned["invalid"], json!({ "broken": { "args": ["x"] } }));
 
-        log::info!("MCP config upgraded successfully.");
-    }
+        // 调用方没有删除的无效条目原样写回，errors / invalid 不写入文件
+        config.mcpServers.remove("good");
+        save_mcp_config(&path, &config).unwrap();
+        let saved = read(&path);
+        assert_eq!(saved["mcpServers"], json!({ "broken": { "args": ["x"] } }));
+        assert!(saved.get("errors").is_none());
+        assert!(saved.get("invalid").is_none());
 
-    Ok(())
+        // 调用方删除的无效条目不再写回
+        let mut config = load_mcp_config(&path).unwrap();
+        config.invalid.remove("broken");
+        save_mcp_config(&path, &config).unwrap();
+        assert_eq!(read(&path)["mcpServers"], json!({}));
+        let _ = std::fs::remove_dir_all(dir);
+    }
 }
@@ -0,0 +1,241 @@
+use crate::constants::{MCP_RUNTIME_MISSING_EVENT, MCP_RUNTIME_PROBE_TIMEOUT};
+use crate::host::Host;
+use crate::mcp::MCPConfig;
+use crate::mcp_server::McpTransport;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Mutex;
+use tauri::{AppHandle, State};
+use tokio::process::Command as TokioCommand;
+
+#[cfg(target_os = "windows")]
+use crate::constants::CREATE_NO_WINDOW;
+
+/// 单个 MCP server 的运行时探测结果
+#[derive(Debug, Clone, Serialize)]
+pub struct McpRuntimeProbe {
+    pub server: String,
+    pub command: String,
+    pub enabled: bool,
+    pub found: bool,
+    pub resolved_path: Option<String>,
+    pub version: Option<String>,
+    pub error: Option<String>,
+}
+
+/// 启动时缺少运行时的 server（事件可能早于前端监听，前端可主动查询）
+pub struct McpRuntimesMissing(pub Mutex<Vec<McpRuntimeProbe>>);
+
+/// 命令本身的探测结果，同一个 command（如 npx）只探测一次
+#[derive(Debug, Clone, Default)]
+struct CommandProbe {
+    resolved_path: Option<PathBuf>,
+    version: Option<String>,
+    error: Option<String>,
+}
+
+/// 需要探测的 stdio server：(名称, command, 是否启用)
+fn stdio_servers(config: &MCPConfig) -> Vec<(String, String, bool)> {
+    config
+        .mcpServers
+        .iter()
+        .filter_map(|(name, server)| match &server.transport {
+            McpTransport::Stdio { command, .. } => {
+                Some((name.clone(), command.clone(), server.enabled()))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path)
+        .map(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    fs::metadata(path).map(|meta| meta.is_file()).unwrap_or(false)
+}
+
+/// 按 host_server 的 PATH 查找命令，行为与 execvp 一致
+///
+/// Windows 上与 cmd 一致先试 PATHEXT 中的扩展名（npx 只有 npx.cmd 可执行，同目录的 npx 是 sh 脚本），最后才试原名。
+fn resolve_command(command: &str, path_env: &str) -> Option<PathBuf> {
+    let extensions: Vec<String> = if cfg!(target_os = "windows") {
+        let pathext = std::env::var("PATHEXT").unwrap_or_else(|_| ".EXE;.CMD;.BAT".into());
+        pathext
+            .split(';')
+            .filter(|ext| !ext.is_empty())
+            .map(|ext| ext.to_lowercase())
+            .chain(std::iter::once(String::new()))
+            .collect()
+    } else {
+        vec![String::new()]
+    };
+    let candidates = |base: PathBuf| {
+        extensions
+            .iter()
+            .map(|ext| PathBuf::from(format!("{}{}", base.to_string_lossy(), ext)))
+            .find(|path| is_executable(path))
+    };
+
+    if command.contains('/') || command.contains('\\') {
+        return candidates(PathBuf::from(command));
+    }
+    let separator = if cfg!(target_os = "windows") { ';' } else { ':' };
+    path_env
+        .split(separator)
+        .filter(|dir| !dir.is_empty())
+        .find_map(|dir| candidates(Path::new(dir).join(command)))
+}
+
+/// 以 host_server 的环境执行 `<command> --version`，取输出的第一行作为版本
+async fn probe_version(path: &Path, env: &BTreeMap<String, String>) -> Result<String, String> {
+    let mut cmd = TokioCommand::new(path);
+    cmd.arg("--version")
+        .env_clear()
+        .envs(env)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    #[cfg(target_os = "windows")]
+    cmd.creation_flags(CREATE_NO_WINDOW);
+
+    let output = tokio::time::timeout(MCP_RUNTIME_PROBE_TIMEOUT, cmd.output())
+        .await
+        .map_err(|_| format!("--version timed out after {:?}", MCP_RUNTIME_PROBE_TIMEOUT))?
+        .map_err(|e| e.to_string())?;
+    let first_line = |bytes: &[u8]| {
+        String::from_utf8_lossy(bytes)
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .map(String::from)
+    };
+    match first_line(&output.stdout).or_else(|| first_line(&output.stderr)) {
+        Some(version) if output.status.success() => Ok(version),
+        Some(line) => Err(format!("--version failed: {}", line)),
+        None => Err(format!("--version printed nothing ({})", output.status)),
+    }
+}
+
+async fn probe_command(command: String, env: BTreeMap<String, String>) -> CommandProbe {
+    let path_env = env.get("PATH").cloned().unwrap_or_default();
+    let resolved_path = match resolve_command(&command, &path_env) {
+        Some(path) => path,
+        None => {
+            return CommandProbe {
+                error: Some(format!("`{}` not found in host_server PATH", command)),
+                ..Default::default()
+            }
+        }
+    };
+    match probe_version(&resolved_path, &env).await {
+        Ok(version) => CommandProbe {
+            resolved_path: Some(resolved_path),
+            version: Some(version),
+            error: None,
+        },
+        Err(e) => CommandProbe {
+            resolved_path: Some(resolved_path),
+            version: None,
+            error: Some(e),
+        },
+    }
+}
+
+/// 探测所有 stdio server 的 command；各命令并行探测
+pub async fn probe_all<H: Host>(app: &H) -> Result<Vec<McpRuntimeProbe>, String> {
+    let path = crate::mcp::get_user_config_path(&app.app_config()).ok_or("配置路径不存在")?;
+    let config = crate::mcp::load_mcp_config(&path)?;
+    let env = crate::host_server::effective_env(app).await?;
+
+    let servers = stdio_servers(&config);
+    let mut tasks = HashMap::new();
+    for (_, command, _) in &servers {
+        if !tasks.contains_key(command) {
+            let task = tokio::spawn(probe_command(command.clone(), env.clone()));
+            tasks.insert(command.clone(), task);
+        }
+    }
+    let mut probes: HashMap<String, CommandProbe> = HashMap::new();
+    for (command, task) in tasks {
+        let probe = task.await.unwrap_or_else(|e| CommandProbe {
+            error: Some(e.to_string()),
+            ..Default::default()
+        });
+        probes.insert(command, probe);
+    }
+
+    Ok(servers
+        .into_iter()
+        .map(|(server, command, enabled)| {
+            let probe = probes.get(&command).cloned().unwrap_or_default();
+            McpRuntimeProbe {
+                server,
+                found: probe.resolved_path.is_some(),
+                resolved_path: probe
+                    .resolved_path
+                    .map(|path| path.to_string_lossy().to_string()),
+                version: probe.version,
+                error: probe.error,
+                command,
+                enabled,
+            }
+        })
+        .collect())
+}
+
+/// 启动时探测一次，启用的 server 找不到命令时通知前端
+pub async fn warn_missing_runtimes<H: Host>(app: H) {
+    let probes = match probe_all(&app).await {
+        Ok(probes) => probes,
+        Err(e) => {
+            log::warn!("Failed to probe MCP runtimes: {}", e);
+            return;
+        }
+    };
+    for probe in &probes {
+        match (&probe.version, &probe.error) {
+            (Some(version), _) => log::info!(
+                "MCP server {}: `{}` -> {} ({})",
+                probe.server,
+                probe.command,
+                probe.resolved_path.as_deref().unwrap_or_default(),
+                version
+            ),
+            (None, Some(e)) => log::warn!("MCP server {}: {}", probe.server, e),
+            (None, None) => {}
+        }
+    }
+    let missing: Vec<McpRuntimeProbe> = probes
+        .into_iter()
+        .filter(|probe| probe.enabled && !probe.found)
+        .collect();
+    *app.mcp_runtimes_missing().0.lock().unwrap() = missing.clone();
+    if !missing.is_empty() {
+        if let Err(e) = app.emit_event(MCP_RUNTIME_MISSING_EVENT, missing) {
+            log::error!("Failed to emit {} to frontend: {}", MCP_RUNTIME_MISSING_EVENT, e);
+        }
+    }
+}
+
+/// 检查每个 MCP server 的运行时（npx / uv / uvx / node 等）是否可用
+#[tauri::command]
+pub async fn probe_mcp_runtimes(app: AppHandle) -> Result<Vec<McpRuntimeProbe>, String> {
+    probe_all(&app).await
+}
+
+/// 启动探测时缺少运行时的已启用 server
+#[tauri::command]
+pub fn get_missing_mcp_runtimes(state: State<'_, McpRuntimesMissing>) -> Vec<McpRuntimeProbe> {
+    state.0.lock().unwrap().clone()
+}
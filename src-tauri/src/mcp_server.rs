@@ -0,0 +1,535 @@
+use crate::schema::{self, JsonSchema};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+
+/// 字段级校验错误，前端按 server + field 显示在对应输入框旁
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldError {
+    /// mcpServers 中的名称；顶层字段为 None
+    pub server: Option<String>,
+    /// 如 url、args[1]、env.API_KEY
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    fn new(server: &str, field: impl Into<String>, message: impl Into<String>) -> Self {
+        FieldError {
+            server: Some(server.to_string()).filter(|s| !s.is_empty()),
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+
+    /// 在整份配置中的位置，如 mcpServers.search.url
+    pub fn path(&self) -> String {
+        match (&self.server, self.field.is_empty()) {
+            (Some(server), true) => format!("mcpServers.{}", server),
+            (Some(server), false) => format!("mcpServers.{}.{}", server, self.field),
+            (None, _) => self.field.clone(),
+        }
+    }
+}
+
+impl std::fmt::Display for FieldError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path(), self.message)
+    }
+}
+
+/// 多个字段错误合并为一条消息（日志、命令行使用）
+pub fn describe_errors(errors: &[FieldError]) -> String {
+    errors
+        .iter()
+        .map(|e| e.to_string())
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// server 的来源：内置、用户添加、远程下发
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AidenType {
+    Default,
+    Custom,
+    Remote,
+}
+
+/// 前端维护的 aiden_* 元数据，host_server 不使用
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AidenMeta {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aiden_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aiden_type: Option<AidenType>,
+    /// 缺省视为启用
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aiden_enable: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aiden_mcp_version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aiden_credential: Option<Value>,
+}
+
+impl JsonSchema for AidenType {
+    fn json_schema() -> Value {
+        schema::string_enum(&["default", "custom", "remote"])
+    }
+}
+
+impl AidenMeta {
+    fn schema_properties() -> Vec<(&'static str, Value)> {
+        vec![
+            ("aiden_id", Option::<String>::json_schema()),
+            ("aiden_type", Option::<AidenType>::json_schema()),
+            ("aiden_enable", Option::<bool>::json_schema()),
+            ("aiden_mcp_version", Option::<String>::json_schema()),
+            ("aiden_credential", Option::<Value>::json_schema()),
+        ]
+    }
+}
+
+const AIDEN_FIELDS: &[&str] = &[
+    "aiden_id",
+    "aiden_type",
+    "aiden_enable",
+    "aiden_mcp_version",
+    "aiden_credential",
+];
+
+/// 连接方式，JSON 中由 transport 字段区分；缺省时有 url 视为 sse，否则为 stdio
+#[derive(Debug, Clone, PartialEq)]
+pub enum McpTransport {
+    Stdio {
+        command: String,
+        args: Vec<String>,
+        env: BTreeMap<String, String>,
+        cwd: Option<String>,
+    },
+    Sse {
+        url: String,
+        headers: BTreeMap<String, String>,
+    },
+    StreamableHttp {
+        url: String,
+        headers: BTreeMap<String, String>,
+    },
+}
+
+impl McpTransport {
+    pub fn name(&self) -> &'static str {
+        match self {
+            McpTransport::Stdio { .. } => "stdio",
+            McpTransport::Sse { .. } => "sse",
+            McpTransport::StreamableHttp { .. } => "streamable_http",
+        }
+    }
+
+    fn fields(&self) -> &'static [&'static str] {
+        match self {
+            McpTransport::Stdio { .. } => &["transport", "command", "args", "env", "cwd"],
+            _ => &["transport", "url", "headers"],
+        }
+    }
+}
+
+/// transport 的各种写法对应的规范名称
+fn transport_name(spelling: &str) -> Option<&'static str> {
+    match spelling.to_lowercase().as_str() {
+        "stdio" => Some("stdio"),
+        "sse" => Some("sse"),
+        "streamable_http" | "streamable-http" | "streamablehttp" | "http" => Some("streamable_http"),
+        _ => None,
+    }
+}
+
+/// 省略 transport 时的推断：有 url 且没有 command 为 sse，否则为 stdio
+fn infer_transport(object: &Map<String, Value>) -> &'static str {
+    if object.contains_key("url") && !object.contains_key("command") {
+        "sse"
+    } else {
+        "stdio"
+    }
+}
+
+/// mcpServers 中的一项
+#[derive(Debug, Clone, PartialEq)]
+pub struct McpServer {
+    pub transport: McpTransport,
+    pub aiden: AidenMeta,
+    /// 未识别的字段，原样写回
+    pub extra: Map<String, Value>,
+    /// 用户写的 transport 原文；None 表示省略。写回时保持原来的写法
+    pub transport_spelling: Option<String>,
+    /// 原配置中写了的可选字段（如空的 args）；写回时只保留这些，不补用户没写的键
+    pub explicit: Vec<String>,
+}
+
+fn string_map(
+    server: &str,
+    field: &str,
+    value: Option<&Value>,
+    errors: &mut Vec<FieldError>,
+) -> BTreeMap<String, String> {
+    let mut map = BTreeMap::new();
+    match value {
+        None | Some(Value::Null) => {}
+        Some(Value::Object(object)) => {
+            for (key, value) in object {
+                match value {
+                    Value::String(value) => {
+                        map.insert(key.clone(), value.clone());
+                    }
+                    _ => errors.push(FieldError::new(
+                        server,
+                        format!("{}.{}", field, key),
+                        "must be a string",
+                    )),
+                }
+            }
+        }
+        Some(_) => errors.push(FieldError::new(server, field, "must be an object of strings")),
+    }
+    map
+}
+
+fn required_string(
+    server: &str,
+    field: &str,
+    value: Option<&Value>,
+    errors: &mut Vec<FieldError>,
+) -> String {
+    match value {
+        Some(Value::String(s)) if !s.trim().is_empty() => s.clone(),
+        Some(Value::String(_)) | None | Some(Value::Null) => {
+            errors.push(FieldError::new(server, field, "is required"));
+            String::new()
+        }
+        Some(_) => {
+            errors.push(FieldError::new(server, field, "must be a string"));
+            String::new()
+        }
+    }
+}
+
+fn parse_url(server: &str, object: &Map<String, Value>, errors: &mut Vec<FieldError>) -> String {
+    let url = required_string(server, "url", object.get("url"), errors);
+    if !url.is_empty() {
+        match reqwest::Url::parse(&url) {
+            Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => {}
+            Ok(_) => errors.push(FieldError::new(server, "url", "must be an http(s) URL")),
+            Err(e) => errors.push(FieldError::new(server, "url", format!("invalid URL: {}", e))),
+        }
+    }
+    url
+}
+
+impl McpServer {
+    /// 校验并解析一项配置，返回该项的全部字段错误
+    pub fn from_value(name: &str, value: &Value) -> Result<McpServer, Vec<FieldError>> {
+        let object = match value.as_object() {
+            Some(object) => object,
+            None => return Err(vec![FieldError::new(name, "", "must be an object")]),
+        };
+        let mut errors = Vec::new();
+
+        let transport_spelling = match object.get("transport") {
+            None | Some(Value::Null) => None,
+            Some(Value::String(s)) => Some(s.clone()),
+            Some(_) => {
+                errors.push(FieldError::new(name, "transport", "must be a string"));
+                None
+            }
+        };
+        let transport_name = match &transport_spelling {
+            Some(spelling) => transport_name(spelling).unwrap_or_default(),
+            None => infer_transport(object),
+        };
+        let transport = match transport_name {
+            "stdio" => {
+                let command = required_string(name, "command", object.get("command"), &mut errors);
+                let args = match object.get("args") {
+                    None | Some(Value::Null) => Vec::new(),
+                    Some(Value::Array(items)) => items
+                        .iter()
+                        .enumerate()
+                        .filter_map(|(i, item)| match item {
+                            Value::String(s) => Some(s.clone()),
+                            _ => {
+                                errors.push(FieldError::new(
+                                    name,
+                                    format!("args[{}]", i),
+                                    "must be a string",
+                                ));
+                                None
+                            }
+                        })
+                        .collect(),
+                    Some(_) => {
+                        errors.push(FieldError::new(name, "args", "must be an array of strings"));
+                        Vec::new()
+                    }
+                };
+                let env = string_map(name, "env", object.get("env"), &mut errors);
+                let cwd = match object.get("cwd") {
+                    None | Some(Value::Null) => None,
+                    Some(Value::String(s)) => Some(s.clone()),
+                    Some(_) => {
+                        errors.push(FieldError::new(name, "cwd", "must be a string"));
+                        None
+                    }
+                };
+                McpTransport::Stdio {
+                    command,
+                    args,
+                    env,
+                    cwd,
+                }
+            }
+            "sse" => McpTransport::Sse {
+                url: parse_url(name, object, &mut errors),
+                headers: string_map(name, "headers", object.get("headers"), &mut errors),
+            },
+            "streamable_http" => McpTransport::StreamableHttp {
+                url: parse_url(name, object, &mut errors),
+                headers: string_map(name, "headers", object.get("headers"), &mut errors),
+            },
+            _ => {
+                errors.push(FieldError::new(
+                    name,
+                    "transport",
+                    format!(
+                        "unknown transport {:?}, expected stdio, sse or streamable_http",
+                        transport_spelling.unwrap_or_default()
+                    ),
+                ));
+                return Err(errors);
+            }
+        };
+
+        let aiden_fields: Map<String, Value> = object
+            .iter()
+            .filter(|(key, _)| AIDEN_FIELDS.contains(&key.as_str()))
+            .filter(|(_, value)| !value.is_null())
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        let mut aiden = AidenMeta::default();
+        for (key, value) in aiden_fields {
+            let single = Value::Object(Map::from_iter([(key.clone(), value)]));
+            match serde_json::from_value::<AidenMeta>(single) {
+                Ok(meta) => match key.as_str() {
+                    "aiden_id" => aiden.aiden_id = meta.aiden_id,
+                    "aiden_type" => aiden.aiden_type = meta.aiden_type,
+                    "aiden_enable" => aiden.aiden_enable = meta.aiden_enable,
+                    "aiden_mcp_version" => aiden.aiden_mcp_version = meta.aiden_mcp_version,
+                    _ => aiden.aiden_credential = meta.aiden_credential,
+                },
+                Err(_) => errors.push(FieldError::new(
+                    name,
+                    key.as_str(),
+                    match key.as_str() {
+                        "aiden_type" => "must be one of default, custom, remote",
+                        "aiden_enable" => "must be true or false",
+                        _ => "must be a string",
+                    },
+                )),
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        let known = transport.fields();
+        let extra = object
+            .iter()
+            .filter(|(key, _)| {
+                !known.contains(&key.as_str()) && !AIDEN_FIELDS.contains(&key.as_str())
+            })
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        let explicit = ["args", "env", "headers"]
+            .iter()
+            .filter(|key| object.get(**key).map_or(false, |value| !value.is_null()))
+            .map(|key| key.to_string())
+            .collect();
+        Ok(McpServer {
+            transport,
+            aiden,
+            extra,
+            transport_spelling,
+            explicit,
+        })
+    }
+
+    pub fn to_value(&self) -> Value {
+        let mut object = self.extra.clone();
+        if let Ok(Value::Object(aiden)) = serde_json::to_value(&self.aiden) {
+            object.extend(aiden);
+        }
+        let optional = |object: &mut Map<String, Value>, key: &str, value: Value, empty: bool| {
+            if !empty || self.explicit.iter().any(|k| k == key) {
+                object.insert(key.into(), value);
+            }
+        };
+        match &self.transport {
+            McpTransport::Stdio {
+                command,
+                args,
+                env,
+                cwd,
+            } => {
+                object.insert("command".into(), Value::from(command.as_str()));
+                optional(&mut object, "args", Value::from(args.clone()), args.is_empty());
+                let env_value = serde_json::to_value(env).unwrap_or_default();
+                optional(&mut object, "env", env_value, env.is_empty());
+                if let Some(cwd) = cwd {
+                    object.insert("cwd".into(), Value::from(cwd.as_str()));
+                }
+            }
+            McpTransport::Sse { url, headers } | McpTransport::StreamableHttp { url, headers } => {
+                object.insert("url".into(), Value::from(url.as_str()));
+                let headers_value = serde_json::to_value(headers).unwrap_or_default();
+                optional(&mut object, "headers", headers_value, headers.is_empty());
+            }
+        }
+        // 用户写了 transport 就按原写法写回；省略时只有推断结果不同才补上
+        let name = self.transport.name();
+        match &self.transport_spelling {
+            Some(spelling) if transport_name(spelling) == Some(name) => {
+                object.insert("transport".into(), Value::from(spelling.as_str()));
+            }
+            None if infer_transport(&object) == name => {}
+            _ => {
+                object.insert("transport".into(), Value::from(name));
+            }
+        }
+        Value::Object(object)
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.aiden.aiden_enable.unwrap_or(true)
+    }
+}
+
+fn url_schema() -> Value {
+    json!({ "type": "string", "format": "uri", "pattern": "^https?://" })
+}
+
+/// 三种连接方式各一个分支；省略 transport 时按 from_value 的规则推断
+impl JsonSchema for McpServer {
+    fn json_schema() -> Value {
+        let variant = |transport: Value, fields: Vec<(&'static str, Value)>, required: &[&str]| {
+            let mut properties = vec![("transport", transport)];
+            properties.extend(fields);
+            properties.extend(AidenMeta::schema_properties());
+            schema::object(properties, required)
+        };
+        let headers = BTreeMap::<String, String>::json_schema();
+        json!({
+            "anyOf": [
+                variant(
+                    schema::string_enum(&["stdio"]),
+                    vec![
+                        ("command", json!({ "type": "string", "minLength": 1 })),
+                        ("args", Vec::<String>::json_schema()),
+                        ("env", BTreeMap::<String, String>::json_schema()),
+                        ("cwd", Option::<String>::json_schema()),
+                    ],
+                    &["command"],
+                ),
+                variant(
+                    schema::string_enum(&["sse"]),
+                    vec![("url", url_schema()), ("headers", headers.clone())],
+                    &["url"],
+                ),
+                variant(
+                    schema::string_enum(&["streamable_http", "streamable-http", "streamablehttp", "http"]),
+                    vec![("url", url_schema()), ("headers", headers)],
+                    &["transport", "url"],
+                ),
+            ]
+        })
+    }
+}
+
+impl Serialize for McpServer {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_value().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for McpServer {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        McpServer::from_value("", &value)
+            .map_err(|errors| serde::de::Error::custom(describe_errors(&errors)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(value: Value) -> Value {
+        McpServer::from_value("test", &value).unwrap().to_value()
+    }
+
+    #[test]
+    fn keeps_transport_spelling() {
+        let value = json!({ "transport": "streamable-http", "url": "https://example.com/mcp" });
+        assert_eq!(round_trip(value.clone()), value);
+        let value = json!({ "transport": "SSE", "url": "https://example.com/sse" });
+        assert_eq!(round_trip(value.clone()), value);
+    }
+
+    #[test]
+    fn does_not_add_keys() {
+        let value = json!({ "command": "npx", "aiden_enable": true });
+        assert_eq!(round_trip(value.clone()), value);
+        let value = json!({ "url": "https://example.com/sse" });
+        assert_eq!(round_trip(value.clone()), value);
+        // 用户写了的空字段保留
+        let value = json!({ "command": "uvx", "args": [], "env": {} });
+        assert_eq!(round_trip(value.clone()), value);
+    }
+
+    #[test]
+    fn adds_transport_only_when_inference_differs() {
+        let mut server =
+            McpServer::from_value("test", &json!({ "url": "https://example.com/mcp" })).unwrap();
+        server.transport = McpTransport::StreamableHttp {
+            url: "https://example.com/mcp".into(),
+            headers: BTreeMap::new(),
+        };
+        assert_eq!(server.to_value()["transport"], "streamable_http");
+    }
+
+    #[test]
+    fn keeps_unknown_fields() {
+        let value = json!({ "command": "npx", "timeout": 30 });
+        let server = McpServer::from_value("test", &value).unwrap();
+        assert_eq!(server.extra.get("timeout"), Some(&json!(30)));
+        assert_eq!(server.to_value(), value);
+    }
+
+    #[test]
+    fn remote_entry_without_url() {
+        let value = json!({ "aiden_type": "remote", "aiden_id": "search", "aiden_enable": true });
+        let errors = McpServer::from_value("search", &value).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].path(), "mcpServers.search.command");
+
+        let errors =
+            McpServer::from_value("search", &json!({ "transport": "sse", "aiden_type": "remote" }))
+                .unwrap_err();
+        assert_eq!(errors[0].path(), "mcpServers.search.url");
+    }
+
+    #[test]
+    fn rejects_non_http_url() {
+        let errors =
+            McpServer::from_value("test", &json!({ "url": "ftp://example.com" })).unwrap_err();
+        assert_eq!(errors[0].field, "url");
+    }
+}
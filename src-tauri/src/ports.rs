@@ -0,0 +1,166 @@
+use crate::constants::PORT_CONFLICT_EVENT;
+use crate::host::Host;
+#[cfg(target_os = "linux")]
+use crate::process;
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::State;
+
+/// 端口被非本应用的进程占用，交给用户处理而不是直接杀掉
+#[derive(Debug, Clone, Serialize)]
+pub struct PortConflict {
+    pub port: u16,
+    pub pid: Option<u32>,
+    pub cmdline: Option<String>,
+}
+
+pub struct PortConflicts(pub Mutex<Vec<PortConflict>>);
+
+/// 解析 /proc/net/tcp{,6}，返回处于 LISTEN 状态的 (端口, socket inode)
+#[cfg(target_os = "linux")]
+fn listening_sockets() -> Vec<(u16, u64)> {
+    let mut sockets = Vec::new();
+    for table in ["/proc/net/tcp", "/proc/net/tcp6"] {
+        let contents = match std::fs::read_to_string(table) {
+            Ok(contents) => contents,
+            Err(_) => continue,
+        };
+        for line in contents.lines().skip(1) {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            // sl local_address rem_address st ... uid timeout inode
+            if fields.len() < 10 || fields[3] != "0A" {
+                continue;
+            }
+            let port = fields[1]
+                .rsplit(':')
+                .next()
+                .and_then(|hex| u16::from_str_radix(hex, 16).ok());
+            let inode = fields[9].parse::<u64>().ok();
+            if let (Some(port), Some(inode)) = (port, inode) {
+                if inode != 0 {
+                    sockets.push((port, inode));
+                }
+            }
+        }
+    }
+    sockets
+}
+
+/// 通过 /proc/<pid>/fd 找出持有这些 socket inode 的进程
+#[cfg(target_os = "linux")]
+fn socket_owners(inodes: &[u64]) -> Vec<(u64, u32)> {
+    let mut owners = Vec::new();
+    for pid in process::all_processes().iter().map(|p| p.pid) {
+        // 其他用户的进程读不到 fd，跳过即可
+        let fds = match std::fs::read_dir(format!("/proc/{}/fd", pid)) {
+            Ok(fds) => fds,
+            Err(_) => continue,
+        };
+        for fd in fds.flatten() {
+            let target = match std::fs::read_link(fd.path()) {
+                Ok(target) => target,
+                Err(_) => continue,
+            };
+            let inode = target
+                .to_str()
+                .and_then(|t| t.strip_prefix("socket:["))
+                .and_then(|t| t.strip_suffix(']'))
+                .and_then(|t| t.parse::<u64>().ok());
+            if let Some(inode) = inode {
+                if inodes.contains(&inode) && !owners.contains(&(inode, pid)) {
+                    owners.push((inode, pid));
+                }
+            }
+        }
+    }
+    owners
+}
+
+/// 找出监听该端口的进程；返回 None 表示端口被占用但无法确定进程（例如属于其他用户）
+#[cfg(target_os = "linux")]
+fn port_holders(port: u16) -> Vec<Option<u32>> {
+    let inodes: Vec<u64> = listening_sockets()
+        .into_iter()
+        .filter(|(p, _)| *p == port)
+        .map(|(_, inode)| inode)
+        .collect();
+    if inodes.is_empty() {
+        return Vec::new();
+    }
+    let owners = socket_owners(&inodes);
+    if owners.is_empty() {
+        return vec![None];
+    }
+    let mut pids: Vec<Option<u32>> = Vec::new();
+    for (_, pid) in owners {
+        if !pids.contains(&Some(pid)) {
+            pids.push(Some(pid));
+        }
+    }
+    pids
+}
+
+#[cfg(not(target_os = "linux"))]
+fn port_holders(port: u16) -> Vec<Option<u32>> {
+    match std::process::Command::new("lsof")
+        .args(["-nP", "-t", &format!("-iTCP:{}", port), "-sTCP:LISTEN"])
+        .output()
+    {
+        Ok(output) => String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|pid| pid.trim().parse::<u32>().ok())
+            .map(Some)
+            .collect(),
+        Err(e) => {
+            log::warn!("Cannot check port {}: lsof is unavailable ({})", port, e);
+            Vec::new()
+        }
+    }
+}
+
+/// 检查固定端口的占用者，全部作为冲突返回。
+///
+/// 上次运行遗留的进程由 cleanup::sweep_orphans 按 run_state 记录结束，需先于本函数调用；
+/// 之后仍占用端口的进程都不是本应用启动的，只报告，不结束。
+pub fn find_port_conflicts(ports: &[u16]) -> Vec<PortConflict> {
+    let mut conflicts = Vec::new();
+    for &port in ports {
+        for holder in port_holders(port) {
+            log::warn!(
+                "Port {} is held by a process we did not start (pid {:?}), leaving it alone",
+                port,
+                holder
+            );
+            // 命令行读自 /proc，其他平台只报告 pid
+            #[cfg(target_os = "linux")]
+            let cmdline = holder
+                .and_then(process::process_info)
+                .map(|current| current.cmdline);
+            #[cfg(not(target_os = "linux"))]
+            let cmdline = None;
+            conflicts.push(PortConflict {
+                port,
+                pid: holder,
+                cmdline,
+            });
+        }
+    }
+    conflicts
+}
+
+/// 启动时检查端口，并把冲突报告给前端
+pub fn report_port_conflicts<H: Host>(app: &H, ports: &[u16]) {
+    let conflicts = find_port_conflicts(ports);
+    if !conflicts.is_empty() {
+        if let Err(e) = app.emit_event(PORT_CONFLICT_EVENT, conflicts.clone()) {
+            log::error!("Failed to emit {} to frontend: {}", PORT_CONFLICT_EVENT, e);
+        }
+    }
+    *app.port_conflicts().0.lock().unwrap() = conflicts;
+}
+
+/// 启动时发现的端口冲突（事件可能早于前端监听，前端可主动查询）
+#[tauri::command]
+pub fn get_port_conflicts(state: State<'_, PortConflicts>) -> Vec<PortConflict> {
+    state.0.lock().unwrap().clone()
+}
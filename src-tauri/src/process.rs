@@ -0,0 +1,313 @@
+use std::process::ExitStatus;
+use std::time::Duration;
+use tokio::process::Child;
+
+#[cfg(target_os = "linux")]
+use nix::sys::signal::kill;
+#[cfg(unix)]
+use nix::sys::signal::{killpg, Signal};
+#[cfg(unix)]
+use nix::unistd::Pid;
+#[cfg(target_os = "linux")]
+use serde::{Deserialize, Serialize};
+#[cfg(target_os = "linux")]
+use std::time::Instant;
+
+/// 一个进程的身份信息；(pid, start_time) 可以唯一标识本次开机内的进程，避免 pid 复用误杀
+///
+/// 进程身份与进程树都读自 /proc，只在 Linux 上可用。其他平台不记录进程树、不清理崩溃遗留的进程，
+/// 停止 host_server 时只按进程组结束（见 terminate_gracefully）。
+#[cfg(target_os = "linux")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub ppid: u32,
+    pub pgid: u32,
+    pub start_time: u64,
+    pub cmdline: String,
+}
+
+/// 读取 /proc/<pid>/stat 与 cmdline，进程不存在或已是僵尸时返回 None
+#[cfg(target_os = "linux")]
+pub fn process_info(pid: u32) -> Option<ProcessInfo> {
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    let cmdline = std::fs::read(format!("/proc/{}/cmdline", pid)).unwrap_or_default();
+    parse_proc(pid, &stat, &cmdline)
+}
+
+/// 解析 /proc/<pid>/stat 与 /proc/<pid>/cmdline 的内容
+#[cfg(target_os = "linux")]
+fn parse_proc(pid: u32, stat: &str, cmdline: &[u8]) -> Option<ProcessInfo> {
+    // comm 字段可能包含空格和括号，从最后一个 ')' 之后开始解析
+    let rest = &stat[stat.rfind(')')? + 1..];
+    let fields: Vec<&str> = rest.split_whitespace().collect();
+    // fields[0] 对应 stat 的第 3 列 (state)；僵尸进程视为已退出
+    if *fields.first()? == "Z" {
+        return None;
+    }
+    let ppid = fields.get(1)?.parse().ok()?;
+    let pgid = fields.get(2)?.parse().ok()?;
+    let start_time = fields.get(19)?.parse().ok()?;
+    let cmdline = cmdline
+        .split(|b| *b == 0)
+        .filter(|part| !part.is_empty())
+        .map(|part| String::from_utf8_lossy(part).into_owned())
+        .collect::<Vec<_>>()
+        .join(" ");
+    Some(ProcessInfo {
+        pid,
+        ppid,
+        pgid,
+        start_time,
+        cmdline,
+    })
+}
+
+/// 列出系统中所有进程
+#[cfg(target_os = "linux")]
+pub fn all_processes() -> Vec<ProcessInfo> {
+    let entries = match std::fs::read_dir("/proc") {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+    entries
+        .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse::<u32>().ok())
+        .filter_map(process_info)
+        .collect()
+}
+
+/// 找出 root 的全部后代进程（不含 root 本身），父进程在前
+#[cfg(target_os = "linux")]
+pub fn descendants(root: u32) -> Vec<ProcessInfo> {
+    let all = all_processes();
+    let mut result: Vec<ProcessInfo> = Vec::new();
+    let mut parents = vec![root];
+    while let Some(parent) = parents.pop() {
+        for process in all.iter().filter(|p| p.ppid == parent) {
+            if process.pid != root && !result.iter().any(|p| p.pid == process.pid) {
+                parents.push(process.pid);
+                result.push(process.clone());
+            }
+        }
+    }
+    result
+}
+
+/// 进程是否仍然是记录时的那一个（pid 未被复用）
+#[cfg(target_os = "linux")]
+pub fn is_same_process(process: &ProcessInfo) -> bool {
+    process_info(process.pid)
+        .map(|current| current.start_time == process.start_time)
+        .unwrap_or(false)
+}
+
+/// 比 is_same_process 更严格，命令行也要一致；用于上一次运行留下的记录（可能跨越重启，start_time 会重复）
+#[cfg(target_os = "linux")]
+pub fn matches_record(process: &ProcessInfo) -> bool {
+    process_info(process.pid)
+        .map(|current| {
+            current.start_time == process.start_time && current.cmdline == process.cmdline
+        })
+        .unwrap_or(false)
+}
+
+/// 结束一组进程：先 SIGTERM，最多等待 grace，仍存活的再 SIGKILL。
+///
+/// 只处理 start_time 与记录一致的进程；会阻塞调用线程。
+#[cfg(target_os = "linux")]
+pub fn kill_processes(name: &str, processes: &[ProcessInfo], grace: Duration) {
+    let own_pid = std::process::id();
+    let targets: Vec<&ProcessInfo> = processes
+        .iter()
+        .filter(|p| p.pid != own_pid && is_same_process(p))
+        .collect();
+    if targets.is_empty() {
+        return;
+    }
+
+    for process in &targets {
+        log::info!(
+            "Sending SIGTERM to {} pid {} ({})",
+            name,
+            process.pid,
+            process.cmdline
+        );
+        let _ = kill(Pid::from_raw(process.pid as i32), Signal::SIGTERM);
+    }
+
+    let deadline = Instant::now() + grace;
+    let mut remaining = targets;
+    loop {
+        remaining.retain(|p| {
+            let alive = is_same_process(p);
+            if !alive {
+                log::info!("{} pid {} exited after SIGTERM", name, p.pid);
+            }
+            alive
+        });
+        if remaining.is_empty() || Instant::now() >= deadline {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+
+    for process in remaining {
+        log::warn!(
+            "{} pid {} did not exit within {:?}, sending SIGKILL",
+            name,
+            process.pid,
+            grace
+        );
+        let _ = kill(Pid::from_raw(process.pid as i32), Signal::SIGKILL);
+    }
+}
+
+/// 描述子进程的退出方式，用于日志
+pub fn describe_exit(status: &ExitStatus) -> String {
+    if let Some(code) = status.code() {
+        return format!("exit code {}", code);
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return match Signal::try_from(signal) {
+                Ok(signal) => format!("signal {}", signal),
+                Err(_) => format!("signal {}", signal),
+            };
+        }
+    }
+    status.to_string()
+}
+
+/// 优雅地停止我们自己启动的子进程：
+/// 先向其进程组发送 SIGTERM，等待 grace 时间，仍未退出再 SIGKILL。
+///
+/// 只作用于 child 所在的进程组（spawn 时以 child 自身为组长），不会误伤其他进程。
+pub async fn terminate_gracefully(
+    name: &str,
+    child: &mut Child,
+    grace: Duration,
+) -> Option<ExitStatus> {
+    let pid = match child.id() {
+        Some(pid) => pid,
+        None => {
+            // 已经被回收
+            return child.try_wait().ok().flatten();
+        }
+    };
+
+    #[cfg(unix)]
+    {
+        let pgid = Pid::from_raw(pid as i32);
+        log::info!("Sending SIGTERM to {} process group {}", name, pgid);
+        if let Err(e) = killpg(pgid, Signal::SIGTERM) {
+            log::warn!("Failed to send SIGTERM to {} ({}): {}", name, pid, e);
+        }
+
+        match tokio::time::timeout(grace, child.wait()).await {
+            Ok(Ok(status)) => {
+                log::info!(
+                    "{} (pid {}) stopped gracefully: {}",
+                    name,
+                    pid,
+                    describe_exit(&status)
+                );
+                // 组内可能还有残留的子进程
+                let _ = killpg(pgid, Signal::SIGKILL);
+                return Some(status);
+            }
+            Ok(Err(e)) => {
+                log::warn!("Failed to wait for {} ({}): {}", name, pid, e);
+            }
+            Err(_) => {
+                log::warn!(
+                    "{} (pid {}) did not exit within {:?}, sending SIGKILL",
+                    name,
+                    pid,
+                    grace
+                );
+            }
+        }
+
+        if let Err(e) = killpg(pgid, Signal::SIGKILL) {
+            log::warn!("Failed to send SIGKILL to {} ({}): {}", name, pid, e);
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = grace;
+        log::info!("Killing {} (pid {})", name, pid);
+    }
+
+    if let Err(e) = child.kill().await {
+        log::warn!("Failed to kill {} ({}): {}", name, pid, e);
+    }
+    let status = child.try_wait().ok().flatten();
+    match &status {
+        Some(status) => log::info!(
+            "{} (pid {}) was killed: {}",
+            name,
+            pid,
+            describe_exit(status)
+        ),
+        None => log::warn!("{} (pid {}) exit status unknown", name, pid),
+    }
+    status
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn parses_stat_with_spaces_and_parens_in_comm() {
+        // 第 3 列起：state ppid pgrp session tty_nr tpgid flags minflt cminflt majflt cmajflt
+        // utime stime cutime cstime priority nice num_threads itrealvalue starttime
+        let stat = "4242 (node (mcp) x) S 4200 4100 4100 0 -1 4194304 0 0 0 0 0 0 0 0 20 0 1 0 987654 0 0";
+        let info = parse_proc(4242, stat, b"npx\0-y\0@scope/server\0").unwrap();
+        assert_eq!(
+            info,
+            ProcessInfo {
+                pid: 4242,
+                ppid: 4200,
+                pgid: 4100,
+                start_time: 987654,
+                cmdline: "npx -y @scope/server".into(),
+            }
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn zombie_is_not_a_process() {
+        let stat = "4242 (node) Z 4200 4100 4100 0 -1 4194304 0 0 0 0 0 0 0 0 20 0 1 0 987654 0 0";
+        assert!(parse_proc(4242, stat, b"").is_none());
+        assert!(parse_proc(4242, "4242 (node) S 4200", b"").is_none());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn record_must_match_start_time_and_cmdline() {
+        let own = process_info(std::process::id()).unwrap();
+        assert!(is_same_process(&own));
+        assert!(matches_record(&own));
+
+        let restarted = ProcessInfo {
+            start_time: own.start_time + 1,
+            ..own.clone()
+        };
+        assert!(!is_same_process(&restarted));
+        assert!(!matches_record(&restarted));
+
+        // pid 与 start_time 相同但命令行不同：重启后撞上的另一个进程
+        let other = ProcessInfo {
+            cmdline: format!("{} --other", own.cmdline),
+            ..own
+        };
+        assert!(is_same_process(&other));
+        assert!(!matches_record(&other));
+    }
+}
@@ -0,0 +1,479 @@
+use crate::constants::{
+    PROCESS_STATS_CHECK_INTERVAL, PROCESS_STATS_CPU_LIMIT_ENV, PROCESS_STATS_CPU_LIMIT_PERCENT,
+    PROCESS_STATS_EVENT, PROCESS_STATS_RSS_LIMIT_ENV, PROCESS_STATS_RSS_LIMIT_MB,
+    PROCESS_STATS_WARNING_EVENT,
+};
+use crate::host::Host;
+use crate::mcp::{self, MCPConfig};
+use crate::mcp_server::{McpServer, McpTransport};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, State};
+
+/// 单个进程的资源占用
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessStat {
+    pub pid: u32,
+    pub ppid: u32,
+    pub rss_bytes: u64,
+    /// 与上一次采样之间的 CPU 占用（100 表示占满一个核）；首次采样为 None
+    pub cpu_percent: Option<f64>,
+    /// 打开的文件描述符数量；无权限或平台不支持时为 None
+    pub open_fds: Option<u32>,
+    pub cmdline: String,
+    /// 对应的 mcpServers 条目
+    pub mcp_server: Option<String>,
+}
+
+/// 一个 MCP server（含其子进程）的资源占用合计
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ServerUsage {
+    pub pids: Vec<u32>,
+    pub rss_bytes: u64,
+    pub cpu_percent: f64,
+    pub open_fds: u32,
+}
+
+/// process_stats 命令与事件的负载
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ProcessStats {
+    pub host_server_pid: Option<u32>,
+    /// host_server 自身及全部后代进程
+    pub processes: Vec<ProcessStat>,
+    pub servers: BTreeMap<String, ServerUsage>,
+}
+
+/// 超过阈值时发出的警告
+#[derive(Debug, Clone, Serialize)]
+pub struct UsageWarning {
+    pub server: String,
+    pub rss_bytes: u64,
+    pub cpu_percent: f64,
+    pub rss_limit_bytes: u64,
+    pub cpu_limit_percent: f64,
+}
+
+/// 采样器状态：计算 CPU 百分比需要上一次的 CPU 时间
+#[derive(Default)]
+pub struct ProcessMonitor(pub Mutex<MonitorState>);
+
+#[derive(Default)]
+pub struct MonitorState {
+    /// (pid, start_time) -> (累计 CPU 秒数, 采样时刻)
+    previous: HashMap<(u32, u64), (f64, Instant)>,
+    /// 已经警告过、尚未回落到阈值以下的 server
+    warned: HashSet<String>,
+    watcher: Option<tauri::async_runtime::JoinHandle<()>>,
+}
+
+/// 平台相关的原始采样结果
+struct RawSample {
+    pid: u32,
+    ppid: u32,
+    start_time: u64,
+    rss_bytes: u64,
+    /// 累计 CPU 时间（秒）
+    cpu_seconds: Option<f64>,
+    /// 平台直接给出的 CPU 百分比（如 ps 的 %cpu）
+    cpu_percent: Option<f64>,
+    open_fds: Option<u32>,
+    cmdline: String,
+}
+
+#[cfg(target_os = "linux")]
+fn sample_tree(root: u32) -> Vec<RawSample> {
+    use crate::process;
+
+    let ticks = nix::unistd::sysconf(nix::unistd::SysconfVar::CLK_TCK)
+        .ok()
+        .flatten()
+        .unwrap_or(100) as f64;
+    let mut pids = vec![root];
+    pids.extend(process::descendants(root).iter().map(|p| p.pid));
+    pids.into_iter()
+        .filter_map(|pid| {
+            let info = process::process_info(pid)?;
+            let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+            let fields: Vec<&str> = stat[stat.rfind(')')? + 1..].split_whitespace().collect();
+            // utime / stime 为 stat 的第 14、15 列
+            let cpu_ticks: Option<u64> = fields
+                .get(11)
+                .zip(fields.get(12))
+                .and_then(|(u, s)| Some(u.parse::<u64>().ok()? + s.parse::<u64>().ok()?));
+            let rss_kb = std::fs::read_to_string(format!("/proc/{}/status", pid))
+                .ok()
+                .and_then(|status| {
+                    status
+                        .lines()
+                        .find_map(|line| line.strip_prefix("VmRSS:"))
+                        .and_then(|value| value.split_whitespace().next()?.parse::<u64>().ok())
+                })
+                .unwrap_or(0);
+            let open_fds = std::fs::read_dir(format!("/proc/{}/fd", pid))
+                .ok()
+                .map(|entries| entries.count() as u32);
+            Some(RawSample {
+                pid,
+                ppid: info.ppid,
+                start_time: info.start_time,
+                rss_bytes: rss_kb * 1024,
+                cpu_seconds: cpu_ticks.map(|t| t as f64 / ticks),
+                cpu_percent: None,
+                open_fds,
+                cmdline: info.cmdline,
+            })
+        })
+        .collect()
+}
+
+/// macOS 等平台没有 /proc，使用 ps 获取进程树与资源占用
+#[cfg(all(unix, not(target_os = "linux")))]
+fn sample_tree(root: u32) -> Vec<RawSample> {
+    let output = match std::process::Command::new("ps")
+        .args(["-A", "-o", "pid=,ppid=,rss=,pcpu=,command="])
+        .output()
+    {
+        Ok(output) => output,
+        Err(e) => {
+            log::warn!("Cannot sample processes: ps is unavailable ({})", e);
+            return Vec::new();
+        }
+    };
+    let all: Vec<RawSample> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let pid = parts.next()?.parse().ok()?;
+            let ppid = parts.next()?.parse().ok()?;
+            let rss_kb: u64 = parts.next()?.parse().ok()?;
+            let cpu_percent = parts.next()?.parse().ok();
+            Some(RawSample {
+                pid,
+                ppid,
+                start_time: 0,
+                rss_bytes: rss_kb * 1024,
+                cpu_seconds: None,
+                cpu_percent,
+                open_fds: None,
+                cmdline: parts.collect::<Vec<_>>().join(" "),
+            })
+        })
+        .collect();
+    let mut tree: Vec<u32> = vec![root];
+    let mut index = 0;
+    while index < tree.len() {
+        let parent = tree[index];
+        tree.extend(all.iter().filter(|p| p.ppid == parent).map(|p| p.pid));
+        index += 1;
+    }
+    all.into_iter().filter(|p| tree.contains(&p.pid)).collect()
+}
+
+#[cfg(not(unix))]
+fn sample_tree(_root: u32) -> Vec<RawSample> {
+    Vec::new()
+}
+
+/// 需要匹配的参数：去掉选项，只保留包名、脚本名等有辨识度的参数；非 stdio server 不对应本地进程
+fn significant_args(server: &McpServer) -> Option<(String, Vec<String>)> {
+    let (command, args) = match &server.transport {
+        McpTransport::Stdio { command, args, .. } => (command, args),
+        _ => return None,
+    };
+    let command = std::path::Path::new(command)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| command.to_string());
+    let args = args
+        .iter()
+        .filter(|arg| !arg.starts_with('-'))
+        .cloned()
+        .collect();
+    Some((command, args))
+}
+
+/// 按 command 与 args 把进程对应到 mcpServers；子进程归属于最近的已匹配祖先
+fn assign_servers(processes: &mut [ProcessStat], config: &MCPConfig) {
+    let matchers: Vec<(String, String, Vec<String>)> = config
+        .mcpServers
+        .iter()
+        .filter_map(|(name, server)| {
+            let (command, args) = significant_args(server)?;
+            Some((name.clone(), command, args))
+        })
+        .collect();
+
+    for process in processes.iter_mut() {
+        let words: Vec<&str> = process.cmdline.split_whitespace().collect();
+        let program = words
+            .first()
+            .and_then(|argv0| std::path::Path::new(argv0).file_name())
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        // 参数最多的匹配最具体（如同为 npx 的多个 server）
+        process.mcp_server = matchers
+            .iter()
+            .filter(|(_, command, args)| {
+                if args.is_empty() {
+                    program == *command
+                } else {
+                    args.iter().all(|arg| words.contains(&arg.as_str()))
+                }
+            })
+            .max_by_key(|(_, _, args)| args.len())
+            .map(|(name, _, _)| name.clone());
+    }
+
+    let parents: HashMap<u32, u32> = processes.iter().map(|p| (p.pid, p.ppid)).collect();
+    let direct: HashMap<u32, String> = processes
+        .iter()
+        .filter_map(|p| Some((p.pid, p.mcp_server.clone()?)))
+        .collect();
+    for process in processes.iter_mut() {
+        let mut pid = process.pid;
+        while process.mcp_server.is_none() {
+            match parents.get(&pid) {
+                Some(parent) => {
+                    pid = *parent;
+                    process.mcp_server = direct.get(&pid).cloned();
+                }
+                None => break,
+            }
+        }
+    }
+}
+
+fn limit_from_env(name: &str, default: f64) -> f64 {
+    std::env::var(name)
+        .ok()
+        .and_then(|value| value.trim().parse::<f64>().ok())
+        .filter(|value| *value > 0.0)
+        .unwrap_or(default)
+}
+
+/// 采样一次 host_server 进程树；会读取 /proc 或运行 ps，在阻塞线程中调用
+pub fn sample<H: Host>(app: &H) -> ProcessStats {
+    let root = app.host_server().0.lock().unwrap().pid;
+    let root = match root {
+        Some(pid) => pid,
+        None => return ProcessStats::default(),
+    };
+    let raw = sample_tree(root);
+    let now = Instant::now();
+
+    let mut monitor = app.process_monitor().0.lock().unwrap();
+    let mut previous = HashMap::new();
+    let mut processes: Vec<ProcessStat> = raw
+        .into_iter()
+        .map(|sample| {
+            let key = (sample.pid, sample.start_time);
+            let cpu_percent = sample.cpu_percent.or_else(|| {
+                let seconds = sample.cpu_seconds?;
+                previous.insert(key, (seconds, now));
+                let (last_seconds, last_at) = monitor.previous.get(&key)?;
+                let elapsed = now.duration_since(*last_at).as_secs_f64();
+                (elapsed > 0.0).then(|| (seconds - last_seconds).max(0.0) / elapsed * 100.0)
+            });
+            ProcessStat {
+                pid: sample.pid,
+                ppid: sample.ppid,
+                rss_bytes: sample.rss_bytes,
+                cpu_percent,
+                open_fds: sample.open_fds,
+                cmdline: sample.cmdline,
+                mcp_server: None,
+            }
+        })
+        .collect();
+    // 只保留仍存在的进程，避免 pid 复用时沿用旧数据
+    monitor.previous = previous;
+    drop(monitor);
+
+    let config = mcp::get_user_config_path(&app.app_config())
+        .and_then(|path| mcp::load_mcp_config(&path).ok());
+    if let Some(config) = &config {
+        assign_servers(&mut processes, config);
+    }
+
+    let mut servers: BTreeMap<String, ServerUsage> = BTreeMap::new();
+    for process in &processes {
+        if let Some(name) = &process.mcp_server {
+            let usage = servers.entry(name.clone()).or_default();
+            usage.pids.push(process.pid);
+            usage.rss_bytes += process.rss_bytes;
+            usage.cpu_percent += process.cpu_percent.unwrap_or(0.0);
+            usage.open_fds += process.open_fds.unwrap_or(0);
+        }
+    }
+    ProcessStats {
+        host_server_pid: Some(root),
+        processes,
+        servers,
+    }
+}
+
+/// 超过阈值时记录警告并通知前端；同一个 server 回落前只警告一次
+fn check_thresholds<H: Host>(app: &H, stats: &ProcessStats) {
+    let rss_limit_bytes =
+        (limit_from_env(PROCESS_STATS_RSS_LIMIT_ENV, PROCESS_STATS_RSS_LIMIT_MB) * 1024.0 * 1024.0)
+            as u64;
+    let cpu_limit_percent =
+        limit_from_env(PROCESS_STATS_CPU_LIMIT_ENV, PROCESS_STATS_CPU_LIMIT_PERCENT);
+
+    let mut monitor = app.process_monitor().0.lock().unwrap();
+    for (server, usage) in &stats.servers {
+        let over = usage.rss_bytes > rss_limit_bytes || usage.cpu_percent > cpu_limit_percent;
+        if !over {
+            monitor.warned.remove(server);
+            continue;
+        }
+        if !monitor.warned.insert(server.clone()) {
+            continue;
+        }
+        log::warn!(
+            "MCP server {} is over its resource limits: rss {} MB, cpu {:.1}% (pids {:?})",
+            server,
+            usage.rss_bytes / 1024 / 1024,
+            usage.cpu_percent,
+            usage.pids
+        );
+        let warning = UsageWarning {
+            server: server.clone(),
+            rss_bytes: usage.rss_bytes,
+            cpu_percent: usage.cpu_percent,
+            rss_limit_bytes,
+            cpu_limit_percent,
+        };
+        if let Err(e) = app.emit_event(PROCESS_STATS_WARNING_EVENT, warning) {
+            log::error!("Failed to emit {}: {}", PROCESS_STATS_WARNING_EVENT, e);
+        }
+    }
+}
+
+/// 采样一次并检查阈值
+async fn sample_and_check<H: Host>(app: &H) -> Result<ProcessStats, String> {
+    let app = app.clone();
+    tokio::task::spawn_blocking(move || {
+        let stats = sample(&app);
+        check_thresholds(&app, &stats);
+        stats
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// 在后台定期检查阈值，随 setup / headless 启动；与前端是否打开资源面板无关
+pub async fn monitor_thresholds<H: Host>(app: H) {
+    let mut ticker = tokio::time::interval(PROCESS_STATS_CHECK_INTERVAL);
+    loop {
+        ticker.tick().await;
+        if let Err(e) = sample_and_check(&app).await {
+            log::warn!("Process stats sampling failed: {}", e);
+        }
+    }
+}
+
+/// host_server 及其 MCP server 的资源占用
+#[tauri::command]
+pub fn process_stats(app: AppHandle) -> ProcessStats {
+    let stats = sample(&app);
+    check_thresholds(&app, &stats);
+    stats
+}
+
+/// 开启（interval_secs > 0）或关闭定期推送 process_stats 事件
+#[tauri::command]
+pub fn watch_process_stats(
+    app: AppHandle,
+    state: State<'_, ProcessMonitor>,
+    interval_secs: Option<u64>,
+) {
+    let mut monitor = state.0.lock().unwrap();
+    if let Some(watcher) = monitor.watcher.take() {
+        watcher.abort();
+    }
+    let interval = match interval_secs {
+        Some(secs) if secs > 0 => Duration::from_secs(secs),
+        _ => {
+            log::info!("Stopped process stats watcher");
+            return;
+        }
+    };
+    log::info!("Emitting process stats every {:?}", interval);
+    monitor.watcher = Some(tauri::async_runtime::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            let stats = match sample_and_check(&app).await {
+                Ok(stats) => stats,
+                Err(e) => {
+                    log::warn!("Process stats sampling failed: {}", e);
+                    continue;
+                }
+            };
+            if let Err(e) = app.emit_event(PROCESS_STATS_EVENT, stats) {
+                log::error!("Failed to emit {}: {}", PROCESS_STATS_EVENT, e);
+            }
+        }
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn process(pid: u32, ppid: u32, cmdline: &str) -> ProcessStat {
+        ProcessStat {
+            pid,
+            ppid,
+            rss_bytes: 0,
+            cpu_percent: None,
+            open_fds: None,
+            cmdline: cmdline.into(),
+            mcp_server: None,
+        }
+    }
+
+    #[test]
+    fn assigns_processes_to_servers() {
+        let config = MCPConfig::from_value(json!({
+            "version": "1.0.0",
+            "syncVersion": "1.0.0",
+            "mcpServers": {
+                "fs": { "command": "npx", "args": ["-y", "@scope/server-fs", "/tmp"] },
+                "git": { "command": "npx", "args": ["-y", "@scope/server-git"] },
+                "local": { "command": "/usr/local/bin/my-mcp" },
+                "remote": { "url": "https://example.com/sse", "transport": "sse" },
+            },
+        }))
+        .unwrap();
+        // host_server -> npx (fs) -> node (fs 的子进程)；npx (git)；my-mcp；无关的 python
+        let mut processes = vec![
+            process(100, 1, "/opt/aiden/host_server --port 6888"),
+            process(101, 100, "npm exec -y @scope/server-fs /tmp"),
+            process(102, 101, "node /home/me/.npm/_npx/1/node_modules/.bin/server-fs /tmp"),
+            process(103, 100, "npm exec -y @scope/server-git"),
+            process(104, 100, "my-mcp"),
+            process(105, 100, "python worker.py"),
+        ];
+        assign_servers(&mut processes, &config);
+        let assigned: Vec<(u32, Option<&str>)> = processes
+            .iter()
+            .map(|p| (p.pid, p.mcp_server.as_deref()))
+            .collect();
+        assert_eq!(
+            assigned,
+            [
+                (100, None),
+                (101, Some("fs")),
+                (102, Some("fs")),
+                (103, Some("git")),
+                (104, Some("local")),
+                (105, None),
+            ]
+        );
+    }
+}
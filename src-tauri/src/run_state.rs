@@ -0,0 +1,68 @@
+use crate::process::ProcessInfo;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use tauri::api::path::app_data_dir;
+use tauri::Config;
+
+/// 本次运行启动的进程记录，用于下次启动时清理上次崩溃遗留的进程
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct RunState {
+    /// 记录这些进程的 Aiden 进程
+    pub app: Option<ProcessInfo>,
+    pub host_server: Option<ProcessInfo>,
+    /// host_server 的后代进程（MCP server 等）
+    #[serde(default)]
+    pub processes: Vec<ProcessInfo>,
+}
+
+/// eg: ~/.local/share/com.aiden.chat/run_state.json
+pub fn get_run_state_path(config: &Config) -> Option<PathBuf> {
+    let mut path = app_data_dir(config)?;
+    fs::create_dir_all(&path).ok()?;
+    path.push("run_state.json");
+    Some(path)
+}
+
+pub fn load(config: &Config) -> Option<RunState> {
+    let path = get_run_state_path(config)?;
+    let contents = fs::read_to_string(path).ok()?;
+    match serde_json::from_str(&contents) {
+        Ok(state) => Some(state),
+        Err(e) => {
+            log::warn!("Ignoring invalid run state file: {}", e);
+            None
+        }
+    }
+}
+
+pub fn save(config: &Config, state: &RunState) {
+    let path = match get_run_state_path(config) {
+        Some(path) => path,
+        None => return,
+    };
+    let result = serde_json::to_string_pretty(state)
+        .map_err(|e| e.to_string())
+        .and_then(|json| fs::write(&path, json).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        log::warn!("Failed to write run state {:?}: {}", path, e);
+    }
+}
+
+/// 记录 host_server 及其当前的后代进程
+pub fn record_host_server(
+    config: &Config,
+    host_server: Option<ProcessInfo>,
+    processes: Vec<ProcessInfo>,
+) {
+    let mut state = load(config).unwrap_or_default();
+    state.app = crate::process::process_info(std::process::id());
+    state.host_server = host_server;
+    state.processes = processes;
+    save(config, &state);
+}
+
+/// 清空进程记录（正常停止后）
+pub fn clear_host_server(config: &Config) {
+    record_host_server(config, None, Vec::new());
+}
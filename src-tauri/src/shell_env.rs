@@ -0,0 +1,252 @@
+use crate::constants::{SHELL_ENV_MARKER, SHELL_PATH_TIMEOUT};
+use crate::host_server::HostServerProcess;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Once;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tauri::api::path::app_data_dir;
+use tauri::{AppHandle, Config, State};
+
+/// 登录 shell 解析出的 PATH，缓存在磁盘上以加快启动
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShellPath {
+    pub shell: String,
+    pub path: String,
+    /// unix 时间戳（秒）
+    pub resolved_at: u64,
+}
+
+/// get_shell_path_info 命令的返回值
+#[derive(Debug, Clone, Serialize)]
+pub struct ShellPathInfo {
+    /// host_server 及 MCP server 实际使用的 PATH（包含内置 bin 目录）
+    pub path: Option<String>,
+    pub entries: Vec<String>,
+    /// 最近一次从登录 shell 解析的结果
+    pub shell: Option<ShellPath>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ShellKind {
+    Bash,
+    Zsh,
+    Fish,
+    Sh,
+}
+
+impl ShellKind {
+    fn from_path(shell: &Path) -> Option<Self> {
+        match shell.file_name()?.to_str()? {
+            "bash" => Some(ShellKind::Bash),
+            "zsh" => Some(ShellKind::Zsh),
+            "fish" => Some(ShellKind::Fish),
+            "sh" | "dash" => Some(ShellKind::Sh),
+            _ => None,
+        }
+    }
+
+    /// 以交互式登录 shell 运行 command，保证加载用户的 profile / rc
+    fn args(self, command: &str) -> Vec<String> {
+        let mut args: Vec<String> = match self {
+            ShellKind::Bash | ShellKind::Zsh | ShellKind::Fish => {
+                vec!["-i".into(), "-l".into()]
+            }
+            // sh 不加 -i：交互式 sh 读取的 $ENV 因实现而异，-l 足以加载 ~/.profile
+            ShellKind::Sh => vec!["-l".into()],
+        };
+        args.push("-c".into());
+        args.push(command.into());
+        args
+    }
+}
+
+/// eg: ~/Library/Application Support/com.aiden.chat/shell_env.json
+pub fn get_cache_path(config: &Config) -> Option<PathBuf> {
+    let mut path = app_data_dir(config)?;
+    fs::create_dir_all(&path).ok()?;
+    path.push("shell_env.json");
+    Some(path)
+}
+
+pub fn load_cache(config: &Config) -> Option<ShellPath> {
+    let contents = fs::read_to_string(get_cache_path(config)?).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+fn save_cache(config: &Config, shell_path: &ShellPath) {
+    let path = match get_cache_path(config) {
+        Some(path) => path,
+        None => return,
+    };
+    let result = serde_json::to_string_pretty(shell_path)
+        .map_err(|e| e.to_string())
+        .and_then(|json| fs::write(&path, json).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        log::warn!("Failed to write shell PATH cache {:?}: {}", path, e);
+    }
+}
+
+/// 用户的登录 shell：优先 $SHELL，其次 passwd，最后 /bin/sh
+#[cfg(unix)]
+fn detect_shell() -> (PathBuf, ShellKind) {
+    let from_env = std::env::var_os("SHELL").map(PathBuf::from);
+    let from_passwd = nix::unistd::User::from_uid(nix::unistd::Uid::current())
+        .ok()
+        .flatten()
+        .map(|user| user.shell);
+    for shell in from_env.into_iter().chain(from_passwd) {
+        if !shell.is_file() {
+            continue;
+        }
+        match ShellKind::from_path(&shell) {
+            Some(kind) => return (shell, kind),
+            None => log::info!("Unsupported login shell {:?}, trying next", shell),
+        }
+    }
+    (PathBuf::from("/bin/sh"), ShellKind::Sh)
+}
+
+/// 从 `env -0` 的输出中取出 PATH；不支持 -0 时退回按行解析
+fn parse_path(stdout: &[u8]) -> Option<String> {
+    let text = String::from_utf8_lossy(stdout);
+    // 交互式 shell 可能在 marker 之前打印欢迎信息
+    let env_output = match text.rfind(SHELL_ENV_MARKER) {
+        Some(index) => &text[index + SHELL_ENV_MARKER.len()..],
+        None => &text[..],
+    };
+    let separator = if env_output.contains('\0') { '\0' } else { '\n' };
+    env_output
+        .split(separator)
+        .find_map(|entry| entry.trim_start_matches('\n').strip_prefix("PATH="))
+        .map(|path| path.trim_end().to_string())
+        .filter(|path| !path.is_empty())
+}
+
+/// 运行登录 shell 解析 PATH，超时后结束整个 shell 进程组
+#[cfg(unix)]
+pub fn resolve() -> Result<ShellPath, String> {
+    use std::io::Read;
+    use std::os::unix::process::CommandExt;
+
+    let (shell, kind) = detect_shell();
+    let command = format!("printf '%s' '{}'; env -0", SHELL_ENV_MARKER);
+    let mut child = Command::new(&shell)
+        .args(kind.args(&command))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .process_group(0)
+        .spawn()
+        .map_err(|e| format!("Failed to run {:?}: {}", shell, e))?;
+
+    // 在单独线程读取 stdout，避免管道写满导致 shell 阻塞
+    let mut stdout = child.stdout.take().ok_or("Failed to capture shell stdout")?;
+    let reader = std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = stdout.read_to_end(&mut buf);
+        buf
+    });
+
+    let deadline = Instant::now() + SHELL_PATH_TIMEOUT;
+    loop {
+        match child.try_wait() {
+            Ok(Some(_)) => break,
+            Ok(None) if Instant::now() >= deadline => {
+                let pgid = nix::unistd::Pid::from_raw(child.id() as i32);
+                let _ = nix::sys::signal::killpg(pgid, nix::sys::signal::Signal::SIGKILL);
+                let _ = child.wait();
+                return Err(format!(
+                    "{:?} did not finish within {:?}",
+                    shell, SHELL_PATH_TIMEOUT
+                ));
+            }
+            Ok(None) => std::thread::sleep(std::time::Duration::from_millis(50)),
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+
+    let output = reader.join().unwrap_or_default();
+    let path = parse_path(&output).ok_or_else(|| format!("No PATH in {:?} output", shell))?;
+    Ok(ShellPath {
+        shell: shell.to_string_lossy().to_string(),
+        path,
+        resolved_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+    })
+}
+
+#[cfg(not(unix))]
+pub fn resolve() -> Result<ShellPath, String> {
+    Err("Login shell PATH discovery is not supported on this platform".into())
+}
+
+/// 本进程内只在后台重新校验一次缓存，host_server 重启等再次调用时不再运行 shell
+static REVALIDATE: Once = Once::new();
+
+/// 获取登录 shell 的 PATH：有缓存时直接使用并在后台重新校验，否则同步解析
+pub fn shell_path(config: &Config) -> Option<String> {
+    if let Some(cached) = load_cache(config) {
+        log::info!(
+            "Using cached login shell PATH from {} (resolved at {})",
+            cached.shell,
+            cached.resolved_at
+        );
+        REVALIDATE.call_once(|| revalidate(config.clone(), cached.path.clone()));
+        return Some(cached.path);
+    }
+
+    match resolve() {
+        Ok(resolved) => {
+            log::info!("Resolved PATH from login shell {}", resolved.shell);
+            save_cache(config, &resolved);
+            // 刚解析过，无需再校验
+            REVALIDATE.call_once(|| {});
+            Some(resolved.path)
+        }
+        Err(e) => {
+            log::warn!("Failed to resolve PATH from login shell: {}", e);
+            None
+        }
+    }
+}
+
+/// 后台重新解析，结果写入缓存，下次启动 host_server 时生效
+fn revalidate(config: Config, cached_path: String) {
+    std::thread::spawn(move || match resolve() {
+        Ok(fresh) => {
+            if fresh.path != cached_path {
+                log::info!(
+                    "Login shell PATH changed, will apply on next host_server start: {}",
+                    fresh.path
+                );
+            }
+            save_cache(&config, &fresh);
+        }
+        Err(e) => log::warn!("Failed to re-validate login shell PATH: {}", e),
+    });
+}
+
+/// 设置页展示 MCP server 实际看到的 PATH
+#[tauri::command]
+pub fn get_shell_path_info(app: AppHandle, state: State<'_, HostServerProcess>) -> ShellPathInfo {
+    let path = state.launch_env().and_then(|env| env.get("PATH").cloned());
+    let separator = if cfg!(target_os = "windows") { ';' } else { ':' };
+    let entries = path
+        .as_deref()
+        .map(|path| {
+            path.split(separator)
+                .filter(|entry| !entry.is_empty())
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default();
+    ShellPathInfo {
+        path,
+        entries,
+        shell: load_cache(&app.config()),
+    }
+}
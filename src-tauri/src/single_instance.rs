@@ -0,0 +1,340 @@
+use crate::constants::{DEEP_LINK_SCHEME, SECOND_INSTANCE_EVENT};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::api::path::app_data_dir;
+use tauri::{AppHandle, Config, Manager, Runtime};
+
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener as LocalListener, UnixStream as LocalStream};
+#[cfg(not(unix))]
+use std::net::{TcpListener as LocalListener, TcpStream as LocalStream};
+
+#[cfg(unix)]
+type LockFile = nix::fcntl::Flock<File>;
+#[cfg(not(unix))]
+type LockFile = File;
+
+const CONNECT_RETRIES: u32 = 10;
+const CONNECT_RETRY_INTERVAL: Duration = Duration::from_millis(200);
+/// 转发连接的读写超时，对方卡住时不会让任何一方挂起
+const FORWARD_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// 已运行实例收到参数后的回复，第二个实例据此提示用户
+const REPLY_WINDOW: &str = "window";
+const REPLY_HEADLESS: &str = "headless";
+
+/// 第二次启动时转发给已运行实例的内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForwardedLaunch {
+    pub args: Vec<String>,
+    pub cwd: Option<String>,
+    /// 参数中的 deep link（如 aiden://...）
+    pub url: Option<String>,
+}
+
+impl ForwardedLaunch {
+    fn current() -> Self {
+        Self::from_args(
+            std::env::args().skip(1).collect(),
+            std::env::current_dir()
+                .ok()
+                .map(|dir| dir.to_string_lossy().to_string()),
+        )
+    }
+
+    fn from_args(args: Vec<String>, cwd: Option<String>) -> Self {
+        let url = args.iter().find(|arg| is_deep_link(arg)).cloned();
+        ForwardedLaunch { args, cwd, url }
+    }
+}
+
+/// 只认本应用 scheme 的位置参数；--host-server-url=http://... 这类选项值不是 deep link
+fn is_deep_link(arg: &str) -> bool {
+    if arg.starts_with('-') {
+        return false;
+    }
+    match arg.split_once("://") {
+        Some((scheme, _)) => scheme.eq_ignore_ascii_case(DEEP_LINK_SCHEME),
+        None => false,
+    }
+}
+
+pub enum Instance {
+    /// 本进程是唯一实例，持有锁和本地 socket
+    Primary(SingleInstance),
+    /// 已有实例在运行，参数已转发
+    Secondary,
+}
+
+/// 持有期间其他实例无法获得锁；drop 时随进程退出自动释放
+pub struct SingleInstance {
+    _lock: LockFile,
+    listener: LocalListener,
+    socket_path: PathBuf,
+}
+
+fn get_instance_dir(config: &Config) -> Option<PathBuf> {
+    let path = app_data_dir(config)?;
+    fs::create_dir_all(&path).ok()?;
+    Some(path)
+}
+
+#[cfg(unix)]
+fn try_lock(path: &PathBuf) -> std::io::Result<Option<LockFile>> {
+    use nix::fcntl::{Flock, FlockArg};
+
+    let file = OpenOptions::new()
+        .create(true)
+        .read(true)
+        .write(true)
+        .open(path)?;
+    match Flock::lock(file, FlockArg::LockExclusiveNonblock) {
+        Ok(lock) => Ok(Some(lock)),
+        Err((_, nix::errno::Errno::EWOULDBLOCK)) => Ok(None),
+        Err((_, e)) => Err(e.into()),
+    }
+}
+
+#[cfg(windows)]
+fn try_lock(path: &PathBuf) -> std::io::Result<Option<LockFile>> {
+    use std::os::windows::fs::OpenOptionsExt;
+
+    // share_mode(0)：其他进程无法再打开该文件
+    match OpenOptions::new()
+        .create(true)
+        .read(true)
+        .write(true)
+        .share_mode(0)
+        .open(path)
+    {
+        Ok(file) => Ok(Some(file)),
+        Err(e) if e.raw_os_error() == Some(32) => Ok(None), // ERROR_SHARING_VIOLATION
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(unix)]
+fn bind(socket_path: &PathBuf) -> std::io::Result<LocalListener> {
+    // 持有锁说明旧 socket 文件一定是残留
+    let _ = fs::remove_file(socket_path);
+    LocalListener::bind(socket_path)
+}
+
+#[cfg(not(unix))]
+fn bind(socket_path: &PathBuf) -> std::io::Result<LocalListener> {
+    // Windows 上使用回环 TCP，端口写入文件供第二个实例读取
+    let listener = LocalListener::bind("127.0.0.1:0")?;
+    fs::write(socket_path, listener.local_addr()?.port().to_string())?;
+    Ok(listener)
+}
+
+#[cfg(unix)]
+fn connect(socket_path: &PathBuf) -> std::io::Result<LocalStream> {
+    LocalStream::connect(socket_path)
+}
+
+#[cfg(not(unix))]
+fn connect(socket_path: &PathBuf) -> std::io::Result<LocalStream> {
+    let port = fs::read_to_string(socket_path)?
+        .trim()
+        .parse::<u16>()
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    LocalStream::connect(("127.0.0.1", port))
+}
+
+fn set_timeouts(stream: &LocalStream) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(FORWARD_TIMEOUT))?;
+    stream.set_write_timeout(Some(FORWARD_TIMEOUT))
+}
+
+/// 发送参数并读取回复；对方没有回复时返回空字符串
+fn send(mut stream: LocalStream, payload: &str) -> std::io::Result<String> {
+    set_timeouts(&stream)?;
+    stream.write_all(format!("{}\n", payload).as_bytes())?;
+    let mut reply = String::new();
+    let _ = BufReader::new(stream).read_line(&mut reply);
+    Ok(reply.trim().to_string())
+}
+
+/// 把本次启动的参数转发给已运行的实例，返回对方的回复
+fn forward(socket_path: &PathBuf, launch: &ForwardedLaunch) -> Result<String, String> {
+    let payload = serde_json::to_string(launch).map_err(|e| e.to_string())?;
+    let mut last_error = String::new();
+    // 已运行的实例可能还在启动中，socket 尚未就绪
+    for _ in 0..CONNECT_RETRIES {
+        match connect(socket_path) {
+            Ok(stream) => return send(stream, &payload).map_err(|e| e.to_string()),
+            Err(e) => last_error = e.to_string(),
+        }
+        std::thread::sleep(CONNECT_RETRY_INTERVAL);
+    }
+    Err(last_error)
+}
+
+/// 获取单实例锁；若已有实例在运行，则转发参数并返回 Secondary
+///
+/// 在 logger 初始化之前调用，因此只使用 println / eprintln。
+pub fn acquire(config: &Config) -> Result<Instance, String> {
+    let dir = get_instance_dir(config).ok_or("Failed to get app data dir")?;
+    let lock_path = dir.join("aiden.lock");
+    let socket_path = dir.join("aiden.sock");
+
+    match try_lock(&lock_path).map_err(|e| format!("Failed to lock {:?}: {}", lock_path, e))? {
+        Some(lock) => {
+            let listener = bind(&socket_path)
+                .map_err(|e| format!("Failed to bind {:?}: {}", socket_path, e))?;
+            Ok(Instance::Primary(SingleInstance {
+                _lock: lock,
+                listener,
+                socket_path,
+            }))
+        }
+        None => {
+            match forward(&socket_path, &ForwardedLaunch::current()).as_deref() {
+                Ok(REPLY_HEADLESS) => println!(
+                    "Aiden is already running headless (no window), forwarded arguments were logged there"
+                ),
+                Ok(_) => println!("Aiden is already running, forwarded arguments to it"),
+                Err(e) => eprintln!("Aiden is already running, but forwarding failed: {}", e),
+            }
+            Ok(Instance::Secondary)
+        }
+    }
+}
+
+fn focus_main_window<R: Runtime>(app: &AppHandle<R>) {
+    if let Some(window) = app.get_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+impl SingleInstance {
+    /// 在后台线程接收其他实例转发来的参数，转成 Tauri 事件并聚焦主窗口
+    pub fn listen<R: Runtime>(self, app: AppHandle<R>) {
+        self.serve(REPLY_WINDOW, move |payload| {
+            focus_main_window(&app);
+            if let Err(e) = app.emit_all(SECOND_INSTANCE_EVENT, payload) {
+                log::error!("Failed to emit {}: {}", SECOND_INSTANCE_EVENT, e);
+            }
+        });
+    }
+
+    /// headless 没有窗口可以聚焦，只记录转发来的参数，并告诉对方本实例是 headless
+    pub fn listen_headless(self) {
+        self.serve(REPLY_HEADLESS, |_| {});
+    }
+
+    fn serve<F>(self, reply: &'static str, handle: F)
+    where
+        F: Fn(ForwardedLaunch) + Send + 'static,
+    {
+        log::info!("Listening for other instances on {:?}", self.socket_path);
+        // self（连同锁）移入线程，一直持有到进程退出
+        std::thread::spawn(move || {
+            for stream in self.listener.incoming() {
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        log::warn!("Failed to accept instance connection: {}", e);
+                        continue;
+                    }
+                };
+                match receive(stream, reply) {
+                    Ok(payload) => {
+                        log::info!("Another instance was launched with {:?}", payload.args);
+                        handle(payload);
+                    }
+                    Err(e) => log::warn!("{}", e),
+                }
+            }
+        });
+    }
+}
+
+/// 读取一次转发的参数并回复；内容无效时不回复，对方读到空字符串
+fn receive(stream: LocalStream, reply: &str) -> Result<ForwardedLaunch, String> {
+    set_timeouts(&stream)
+        .map_err(|e| format!("Failed to set instance connection timeouts: {}", e))?;
+    let mut line = String::new();
+    let mut reader = BufReader::new(stream);
+    reader
+        .read_line(&mut line)
+        .map_err(|e| format!("Failed to read forwarded arguments: {}", e))?;
+    let payload = serde_json::from_str::<ForwardedLaunch>(line.trim())
+        .map_err(|e| format!("Ignoring invalid forwarded payload: {}", e))?;
+    if let Err(e) = reader.get_mut().write_all(format!("{}\n", reply).as_bytes()) {
+        log::debug!("Failed to reply to the other instance: {}", e);
+    }
+    Ok(payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn only_app_scheme_positional_args_are_deep_links() {
+        let launch = ForwardedLaunch::from_args(
+            args(&["--host-server-url=http://127.0.0.1:6888", "https://example.com"]),
+            None,
+        );
+        assert_eq!(launch.url, None);
+
+        let launch = ForwardedLaunch::from_args(
+            args(&["--host-server-url=aiden://x", "AIDEN://open?agent=1", "aiden://second"]),
+            None,
+        );
+        assert_eq!(launch.url.as_deref(), Some("AIDEN://open?agent=1"));
+        assert_eq!(launch.args.len(), 3);
+
+        assert!(!is_deep_link("aiden:open"));
+        assert!(!is_deep_link("-aiden://x"));
+    }
+
+    /// 在临时目录中起一个监听端，只处理一个连接
+    fn serve_once(
+        name: &str,
+        reply: &'static str,
+    ) -> (PathBuf, std::thread::JoinHandle<Result<ForwardedLaunch, String>>) {
+        let dir =
+            std::env::temp_dir().join(format!("aiden-instance-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let socket_path = dir.join("aiden.sock");
+        let listener = bind(&socket_path).unwrap();
+        let handle = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().map_err(|e| e.to_string())?;
+            receive(stream, reply)
+        });
+        (socket_path, handle)
+    }
+
+    #[test]
+    fn forwards_launch_and_reads_reply() {
+        let (socket_path, handle) = serve_once("forward", REPLY_HEADLESS);
+        let launch = ForwardedLaunch::from_args(args(&["aiden://open"]), Some("/work".into()));
+        assert_eq!(forward(&socket_path, &launch).unwrap(), REPLY_HEADLESS);
+        let received = handle.join().unwrap().unwrap();
+        assert_eq!(received.args, launch.args);
+        assert_eq!(received.cwd.as_deref(), Some("/work"));
+        assert_eq!(received.url.as_deref(), Some("aiden://open"));
+        let _ = fs::remove_dir_all(socket_path.parent().unwrap());
+    }
+
+    #[test]
+    fn invalid_payload_gets_no_reply() {
+        let (socket_path, handle) = serve_once("invalid", REPLY_WINDOW);
+        let reply = send(connect(&socket_path).unwrap(), "not json").unwrap();
+        assert_eq!(reply, "");
+        assert!(handle.join().unwrap().is_err());
+        let _ = fs::remove_dir_all(socket_path.parent().unwrap());
+    }
+}